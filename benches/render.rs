@@ -0,0 +1,54 @@
+use chalk_rs::{Chalk, ColorLevel};
+use chalk_rs_baseline::Chalk as BaselineChalk;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use std::fmt::Write;
+
+fn render(c: &mut Criterion) {
+	const STYLED: Chalk = Chalk::new()
+		.with_bold()
+		.with_underline()
+		.with_red()
+		.with_bg_blue();
+	let mut baseline_styled = BaselineChalk::new();
+	baseline_styled.bold().underline().red().bg_blue();
+	let baseline_default = BaselineChalk::new();
+	let mut out = String::new();
+
+	let mut group = c.benchmark_group("styled");
+	group.bench_function("baseline", |b| {
+		b.iter(|| {
+			out.clear();
+			let chalk = black_box(&baseline_styled);
+			out.push_str(&chalk.string(&black_box("text")));
+		})
+	});
+	group.bench_function("single sequence", |b| {
+		b.iter(|| {
+			out.clear();
+			let painted = black_box(STYLED).paint(black_box("text"));
+			write!(out, "{}", painted.at_level(ColorLevel::Basic)).unwrap();
+		})
+	});
+	group.finish();
+
+	let mut group = c.benchmark_group("default");
+	group.bench_function("baseline", |b| {
+		b.iter(|| {
+			out.clear();
+			let chalk = black_box(&baseline_default);
+			out.push_str(&chalk.string(&black_box("text")));
+		})
+	});
+	group.bench_function("single sequence", |b| {
+		b.iter(|| {
+			out.clear();
+			let painted = black_box(Chalk::new()).paint(black_box("text"));
+			write!(out, "{}", painted.at_level(ColorLevel::Basic)).unwrap();
+		})
+	});
+	group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+extern crate chalk_rs;
+use chalk_rs::{Gradient, Interpolation, RgbColor};
+
+fn main() {
+	let line = "r g b ".repeat(13);
+	let stops = [
+		RgbColor::new(255, 0, 0),
+		RgbColor::new(0, 255, 0),
+		RgbColor::new(0, 0, 255),
+	];
+
+	// foreground colors, in each color space
+	let mut gradient = Gradient::new(stops);
+	for &interpolation in &[
+		Interpolation::Srgb,
+		Interpolation::LinearRgb,
+		Interpolation::Hsl,
+		Interpolation::Oklab,
+	] {
+		gradient.interpolation(interpolation);
+		println!("{}", gradient.apply(&line));
+	}
+
+	// background color
+	gradient.background();
+	println!("{}", gradient.apply(&line));
+
+	// rainbows
+	println!("{}", Gradient::rainbow().apply(&line));
+	let block = vec![" ".repeat(78); 6].join("\n");
+	println!(
+		"{}",
+		Gradient::hue_cycle(0.0).background().apply_diagonal(&block)
+	);
+}
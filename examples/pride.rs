@@ -8,8 +8,8 @@ fn main() {
 	let mut chalk = Chalk::new();
 	let mut line = |color: u8| chalk.bg_ansi(color).println(&[' '; LINE_LENGTH].iter().collect::<String>());
 	let mut print_lines = |colors: &[u8]| {
-		for i in 0..colors.len() {
-			line(colors[i]);
+		for &color in colors {
+			line(color);
 		}
 		println!();
 	};
@@ -1,3 +1,10 @@
+use crate::basic_chalk::BasicColor;
+use crate::color_names::{parse_ansi, ColorParseError, XTERM_NAMES};
+use crate::rgb_chalk::{RgbColor, BASIC_COLORS, CUBE_LEVELS};
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
 #[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct AnsiColor(u8);
 
@@ -11,4 +18,67 @@ impl AnsiColor {
 	pub const fn as_num(self) -> u8 {
 		self.0
 	}
+
+	/// Gets the standard xterm value of the color
+	pub const fn to_rgb(self) -> RgbColor {
+		match self.0 {
+			0..=15 => BASIC_COLORS[self.0 as usize],
+			16..=231 => {
+				let index = self.0 - 16;
+				RgbColor::new(
+					CUBE_LEVELS[(index / 36) as usize],
+					CUBE_LEVELS[(index / 6 % 6) as usize],
+					CUBE_LEVELS[(index % 6) as usize],
+				)
+			}
+			_ => {
+				let gray = 8 + 10 * (self.0 - 232);
+				RgbColor::new(gray, gray, gray)
+			}
+		}
+	}
+
+	/// Finds the closest of the 16 basic colors
+	pub fn to_basic(self) -> BasicColor {
+		if self.0 < 16 {
+			BasicColor::from_index(self.0)
+		} else {
+			self.to_rgb().to_basic()
+		}
+	}
+
+	/// Gets the name xterm gives to the color, such as `DarkOrange3`.
+	///
+	/// Some names are used by more than one color.
+	pub const fn xterm_name(self) -> &'static str {
+		XTERM_NAMES[self.0 as usize]
+	}
+}
+
+/// Parses a palette index, such as `208`, or an xterm color name, such as
+/// `DarkOrange3`. Names are case insensitive.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::AnsiColor;
+///
+/// let color: AnsiColor = "DarkOrange3".parse().unwrap();
+/// assert_eq!(color.as_num(), 130);
+/// assert_eq!(AnsiColor::from_num(208).xterm_name(), "DarkOrange");
+/// ```
+impl FromStr for AnsiColor {
+	type Err = ColorParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		parse_ansi(s)
+	}
+}
+
+impl TryFrom<&str> for AnsiColor {
+	type Error = ColorParseError;
+
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		parse_ansi(s)
+	}
 }
@@ -0,0 +1,204 @@
+use crate::{Chalk, ChalkType, ColorLevel, Oklab, Palette, RgbColor, Terminal};
+
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const UNKNOWN: u8 = u8::MAX;
+
+static DETECTED: AtomicU8 = AtomicU8::new(UNKNOWN);
+static OVERRIDE: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// Backgrounds which are lighter than this, in OKLab, are light
+const LIGHT_THRESHOLD: f32 = 0.6;
+
+/// Whether the terminal has a light or dark background
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Appearance {
+	/// Dark text on a light background
+	Light,
+	/// Light text on a dark background
+	Dark,
+}
+
+impl Appearance {
+	const fn from_u8(appearance: u8) -> Option<Self> {
+		match appearance {
+			0 => Some(Appearance::Light),
+			1 => Some(Appearance::Dark),
+			_ => None,
+		}
+	}
+
+	/// Gets the appearance of a background color
+	pub fn of(background: RgbColor) -> Self {
+		if Oklab::from(background).l > LIGHT_THRESHOLD {
+			Appearance::Light
+		} else {
+			Appearance::Dark
+		}
+	}
+
+	/// Gets the appearance from the value of the `COLORFGBG` variable, which
+	/// some terminals set to the foreground and background colors, such as
+	/// `15;0`
+	pub fn from_colorfgbg(value: &str) -> Option<Self> {
+		let background = value.rsplit(';').next()?.parse().ok()?;
+		Some(Self::of(Palette::xterm().get(background)))
+	}
+
+	/// Gets the appearance of the terminal.
+	///
+	/// The terminal is asked for its background color the first time this
+	/// is called, but only if standard output shows colors. If it doesn't
+	/// reply, the `COLORFGBG` variable is used instead. Terminals are assumed
+	/// to be dark if neither of these work. An override set with
+	/// [`Appearance::set_override`] takes precedence.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Appearance, Chalk};
+	///
+	/// let mut chalk = Chalk::new();
+	/// match Appearance::detect() {
+	/// 	Appearance::Light => chalk.blue(),
+	/// 	Appearance::Dark => chalk.light_cyan(),
+	/// };
+	/// ```
+	pub fn detect() -> Self {
+		let forced = OVERRIDE.load(Ordering::Relaxed);
+		if let Some(appearance) = Self::from_u8(forced) {
+			return appearance;
+		}
+
+		let cached = DETECTED.load(Ordering::Relaxed);
+		if let Some(appearance) = Self::from_u8(cached) {
+			return appearance;
+		}
+
+		let from_env = || Self::from_colorfgbg(&env::var("COLORFGBG").ok()?);
+		if !ColorLevel::stdout().has_color() {
+			// the colors won't be seen, so the terminal isn't worth asking
+			return from_env().unwrap_or(Appearance::Dark);
+		}
+
+		let appearance = Terminal::open()
+			.and_then(|mut terminal| terminal.background())
+			.map(Self::of)
+			.ok()
+			.or_else(from_env)
+			.unwrap_or(Appearance::Dark);
+		DETECTED.store(appearance as u8, Ordering::Relaxed);
+		appearance
+	}
+
+	/// Forces the appearance of the terminal, or goes back to automatic
+	/// detection if `None` is given
+	pub fn set_override(appearance: Option<Appearance>) {
+		let appearance = appearance.map_or(UNKNOWN, |a| a as u8);
+		OVERRIDE.store(appearance, Ordering::Relaxed);
+	}
+}
+
+/// A color which depends on whether the terminal is light or dark.
+///
+/// The color is picked with [`AdaptiveColor::resolve`], or set on a chalk
+/// with [`Chalk::adaptive_fg`], given an appearance such as the one from
+/// [`Appearance::detect`].
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{AdaptiveColor, Appearance, BasicColor, Chalk, ChalkType};
+///
+/// let warning = AdaptiveColor::new(BasicColor::Yellow, BasicColor::LightYellow);
+/// assert_eq!(
+/// 	warning.resolve(Appearance::Light),
+/// 	ChalkType::Basic(BasicColor::Yellow)
+/// );
+///
+/// let mut chalk = Chalk::new();
+/// chalk.adaptive_fg(&warning, Appearance::detect()).println(&"careful");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AdaptiveColor {
+	/// The color used on light backgrounds
+	pub light: ChalkType,
+	/// The color used on dark backgrounds
+	pub dark: ChalkType,
+}
+
+impl AdaptiveColor {
+	/// Creates a color from the colors to use on light and dark backgrounds
+	pub fn new(
+		light: impl Into<ChalkType>,
+		dark: impl Into<ChalkType>,
+	) -> Self {
+		AdaptiveColor {
+			light: light.into(),
+			dark: dark.into(),
+		}
+	}
+
+	/// Gets the color to use for an appearance
+	pub fn resolve(&self, appearance: Appearance) -> ChalkType {
+		match appearance {
+			Appearance::Light => self.light,
+			Appearance::Dark => self.dark,
+		}
+	}
+}
+
+/** Adaptive colors */
+impl Chalk {
+	/// Sets the foreground to the color for an appearance
+	#[inline(always)]
+	pub fn adaptive_fg(
+		&mut self,
+		color: &AdaptiveColor,
+		appearance: Appearance,
+	) -> &mut Self {
+		self.fg(color.resolve(appearance))
+	}
+
+	/// Sets the background to the color for an appearance
+	#[inline(always)]
+	pub fn adaptive_bg(
+		&mut self,
+		color: &AdaptiveColor,
+		appearance: Appearance,
+	) -> &mut Self {
+		self.bg(color.resolve(appearance))
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+	use crate::BasicColor;
+
+	#[test]
+	fn appearance_of_colors() {
+		assert_eq!(Appearance::of(RgbColor::new(0, 43, 54)), Appearance::Dark);
+		let solarized_light = RgbColor::new(253, 246, 227);
+		assert_eq!(Appearance::of(solarized_light), Appearance::Light);
+
+		let light = Some(Appearance::Light);
+		assert_eq!(Appearance::from_colorfgbg("0;15"), light);
+		assert_eq!(Appearance::from_colorfgbg("0;default;7"), light);
+		let dark = Some(Appearance::Dark);
+		assert_eq!(Appearance::from_colorfgbg("15;0"), dark);
+		assert_eq!(Appearance::from_colorfgbg("default;default"), None);
+	}
+
+	#[test]
+	fn adaptive_colors() {
+		let color = AdaptiveColor::new(BasicColor::Blue, BasicColor::LightCyan);
+		let mut chalk = Chalk::new();
+		chalk.adaptive_fg(&color, Appearance::Light);
+		assert_eq!(chalk.foreground(), &ChalkType::blue());
+		chalk.adaptive_bg(&color, Appearance::Dark);
+		assert_eq!(chalk.background(), &ChalkType::light_cyan());
+	}
+}
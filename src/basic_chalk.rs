@@ -29,4 +29,36 @@ impl BasicColor {
 	pub const fn as_background_color(self) -> u8 {
 		self as u8 + 10
 	}
+
+	/// Gets the color with the given index in the xterm palette.
+	///
+	/// Indices above 15 are wrapped around.
+	pub const fn from_index(index: u8) -> Self {
+		match index % 16 {
+			0 => BasicColor::Black,
+			1 => BasicColor::Red,
+			2 => BasicColor::Green,
+			3 => BasicColor::Yellow,
+			4 => BasicColor::Blue,
+			5 => BasicColor::Magenta,
+			6 => BasicColor::Cyan,
+			7 => BasicColor::LightGray,
+			8 => BasicColor::Gray,
+			9 => BasicColor::LightRed,
+			10 => BasicColor::LightGreen,
+			11 => BasicColor::LightYellow,
+			12 => BasicColor::LightBlue,
+			13 => BasicColor::LightMagenta,
+			14 => BasicColor::LightCyan,
+			_ => BasicColor::White,
+		}
+	}
+
+	/// Gets the index of the color in the xterm palette
+	pub const fn index(self) -> u8 {
+		match self as u8 {
+			code @ 30..=37 => code - 30,
+			code => code - 90 + 8,
+		}
+	}
 }
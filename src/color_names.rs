@@ -0,0 +1,662 @@
+use crate::{AnsiColor, RgbColor};
+
+use std::error::Error;
+use std::fmt::Display;
+
+/// The reason a color couldn't be parsed
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ColorParseErrorKind {
+	/// Nothing was given
+	Empty,
+	/// A `#` color which isn't made of 3 or 6 hexadecimal digits
+	InvalidHex,
+	/// An `rgb()` color without three valid channels and an optional alpha
+	InvalidFunction,
+	/// A channel or palette index which is too large
+	OutOfRange,
+	/// A name which isn't a known color
+	UnknownName,
+}
+
+/// An error from parsing an [`RgbColor`] or [`AnsiColor`]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ColorParseError {
+	kind: ColorParseErrorKind,
+	input: String,
+}
+
+impl ColorParseError {
+	pub(crate) fn new(kind: ColorParseErrorKind, input: &str) -> Self {
+		ColorParseError {
+			kind,
+			input: input.to_string(),
+		}
+	}
+
+	/// What went wrong
+	pub const fn kind(&self) -> ColorParseErrorKind {
+		self.kind
+	}
+
+	/// The text which couldn't be parsed
+	pub fn input(&self) -> &str {
+		&self.input
+	}
+}
+
+impl Display for ColorParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let reason = match self.kind {
+			ColorParseErrorKind::Empty => "no color was given",
+			ColorParseErrorKind::InvalidHex => "invalid hex color",
+			ColorParseErrorKind::InvalidFunction => "invalid rgb() color",
+			ColorParseErrorKind::OutOfRange => "color value is out of range",
+			ColorParseErrorKind::UnknownName => "unknown color name",
+		};
+
+		write!(f, "{}: `{}`", reason, self.input)
+	}
+}
+
+impl Error for ColorParseError {}
+
+/// The named colors from CSS
+pub(crate) const CSS_COLORS: [(&str, RgbColor); 148] = [
+	("aliceblue", RgbColor::new(240, 248, 255)),
+	("antiquewhite", RgbColor::new(250, 235, 215)),
+	("aqua", RgbColor::new(0, 255, 255)),
+	("aquamarine", RgbColor::new(127, 255, 212)),
+	("azure", RgbColor::new(240, 255, 255)),
+	("beige", RgbColor::new(245, 245, 220)),
+	("bisque", RgbColor::new(255, 228, 196)),
+	("black", RgbColor::new(0, 0, 0)),
+	("blanchedalmond", RgbColor::new(255, 235, 205)),
+	("blue", RgbColor::new(0, 0, 255)),
+	("blueviolet", RgbColor::new(138, 43, 226)),
+	("brown", RgbColor::new(165, 42, 42)),
+	("burlywood", RgbColor::new(222, 184, 135)),
+	("cadetblue", RgbColor::new(95, 158, 160)),
+	("chartreuse", RgbColor::new(127, 255, 0)),
+	("chocolate", RgbColor::new(210, 105, 30)),
+	("coral", RgbColor::new(255, 127, 80)),
+	("cornflowerblue", RgbColor::new(100, 149, 237)),
+	("cornsilk", RgbColor::new(255, 248, 220)),
+	("crimson", RgbColor::new(220, 20, 60)),
+	("cyan", RgbColor::new(0, 255, 255)),
+	("darkblue", RgbColor::new(0, 0, 139)),
+	("darkcyan", RgbColor::new(0, 139, 139)),
+	("darkgoldenrod", RgbColor::new(184, 134, 11)),
+	("darkgray", RgbColor::new(169, 169, 169)),
+	("darkgreen", RgbColor::new(0, 100, 0)),
+	("darkgrey", RgbColor::new(169, 169, 169)),
+	("darkkhaki", RgbColor::new(189, 183, 107)),
+	("darkmagenta", RgbColor::new(139, 0, 139)),
+	("darkolivegreen", RgbColor::new(85, 107, 47)),
+	("darkorange", RgbColor::new(255, 140, 0)),
+	("darkorchid", RgbColor::new(153, 50, 204)),
+	("darkred", RgbColor::new(139, 0, 0)),
+	("darksalmon", RgbColor::new(233, 150, 122)),
+	("darkseagreen", RgbColor::new(143, 188, 143)),
+	("darkslateblue", RgbColor::new(72, 61, 139)),
+	("darkslategray", RgbColor::new(47, 79, 79)),
+	("darkslategrey", RgbColor::new(47, 79, 79)),
+	("darkturquoise", RgbColor::new(0, 206, 209)),
+	("darkviolet", RgbColor::new(148, 0, 211)),
+	("deeppink", RgbColor::new(255, 20, 147)),
+	("deepskyblue", RgbColor::new(0, 191, 255)),
+	("dimgray", RgbColor::new(105, 105, 105)),
+	("dimgrey", RgbColor::new(105, 105, 105)),
+	("dodgerblue", RgbColor::new(30, 144, 255)),
+	("firebrick", RgbColor::new(178, 34, 34)),
+	("floralwhite", RgbColor::new(255, 250, 240)),
+	("forestgreen", RgbColor::new(34, 139, 34)),
+	("fuchsia", RgbColor::new(255, 0, 255)),
+	("gainsboro", RgbColor::new(220, 220, 220)),
+	("ghostwhite", RgbColor::new(248, 248, 255)),
+	("gold", RgbColor::new(255, 215, 0)),
+	("goldenrod", RgbColor::new(218, 165, 32)),
+	("gray", RgbColor::new(128, 128, 128)),
+	("green", RgbColor::new(0, 128, 0)),
+	("greenyellow", RgbColor::new(173, 255, 47)),
+	("grey", RgbColor::new(128, 128, 128)),
+	("honeydew", RgbColor::new(240, 255, 240)),
+	("hotpink", RgbColor::new(255, 105, 180)),
+	("indianred", RgbColor::new(205, 92, 92)),
+	("indigo", RgbColor::new(75, 0, 130)),
+	("ivory", RgbColor::new(255, 255, 240)),
+	("khaki", RgbColor::new(240, 230, 140)),
+	("lavender", RgbColor::new(230, 230, 250)),
+	("lavenderblush", RgbColor::new(255, 240, 245)),
+	("lawngreen", RgbColor::new(124, 252, 0)),
+	("lemonchiffon", RgbColor::new(255, 250, 205)),
+	("lightblue", RgbColor::new(173, 216, 230)),
+	("lightcoral", RgbColor::new(240, 128, 128)),
+	("lightcyan", RgbColor::new(224, 255, 255)),
+	("lightgoldenrodyellow", RgbColor::new(250, 250, 210)),
+	("lightgray", RgbColor::new(211, 211, 211)),
+	("lightgreen", RgbColor::new(144, 238, 144)),
+	("lightgrey", RgbColor::new(211, 211, 211)),
+	("lightpink", RgbColor::new(255, 182, 193)),
+	("lightsalmon", RgbColor::new(255, 160, 122)),
+	("lightseagreen", RgbColor::new(32, 178, 170)),
+	("lightskyblue", RgbColor::new(135, 206, 250)),
+	("lightslategray", RgbColor::new(119, 136, 153)),
+	("lightslategrey", RgbColor::new(119, 136, 153)),
+	("lightsteelblue", RgbColor::new(176, 196, 222)),
+	("lightyellow", RgbColor::new(255, 255, 224)),
+	("lime", RgbColor::new(0, 255, 0)),
+	("limegreen", RgbColor::new(50, 205, 50)),
+	("linen", RgbColor::new(250, 240, 230)),
+	("magenta", RgbColor::new(255, 0, 255)),
+	("maroon", RgbColor::new(128, 0, 0)),
+	("mediumaquamarine", RgbColor::new(102, 205, 170)),
+	("mediumblue", RgbColor::new(0, 0, 205)),
+	("mediumorchid", RgbColor::new(186, 85, 211)),
+	("mediumpurple", RgbColor::new(147, 112, 219)),
+	("mediumseagreen", RgbColor::new(60, 179, 113)),
+	("mediumslateblue", RgbColor::new(123, 104, 238)),
+	("mediumspringgreen", RgbColor::new(0, 250, 154)),
+	("mediumturquoise", RgbColor::new(72, 209, 204)),
+	("mediumvioletred", RgbColor::new(199, 21, 133)),
+	("midnightblue", RgbColor::new(25, 25, 112)),
+	("mintcream", RgbColor::new(245, 255, 250)),
+	("mistyrose", RgbColor::new(255, 228, 225)),
+	("moccasin", RgbColor::new(255, 228, 181)),
+	("navajowhite", RgbColor::new(255, 222, 173)),
+	("navy", RgbColor::new(0, 0, 128)),
+	("oldlace", RgbColor::new(253, 245, 230)),
+	("olive", RgbColor::new(128, 128, 0)),
+	("olivedrab", RgbColor::new(107, 142, 35)),
+	("orange", RgbColor::new(255, 165, 0)),
+	("orangered", RgbColor::new(255, 69, 0)),
+	("orchid", RgbColor::new(218, 112, 214)),
+	("palegoldenrod", RgbColor::new(238, 232, 170)),
+	("palegreen", RgbColor::new(152, 251, 152)),
+	("paleturquoise", RgbColor::new(175, 238, 238)),
+	("palevioletred", RgbColor::new(219, 112, 147)),
+	("papayawhip", RgbColor::new(255, 239, 213)),
+	("peachpuff", RgbColor::new(255, 218, 185)),
+	("peru", RgbColor::new(205, 133, 63)),
+	("pink", RgbColor::new(255, 192, 203)),
+	("plum", RgbColor::new(221, 160, 221)),
+	("powderblue", RgbColor::new(176, 224, 230)),
+	("purple", RgbColor::new(128, 0, 128)),
+	("rebeccapurple", RgbColor::new(102, 51, 153)),
+	("red", RgbColor::new(255, 0, 0)),
+	("rosybrown", RgbColor::new(188, 143, 143)),
+	("royalblue", RgbColor::new(65, 105, 225)),
+	("saddlebrown", RgbColor::new(139, 69, 19)),
+	("salmon", RgbColor::new(250, 128, 114)),
+	("sandybrown", RgbColor::new(244, 164, 96)),
+	("seagreen", RgbColor::new(46, 139, 87)),
+	("seashell", RgbColor::new(255, 245, 238)),
+	("sienna", RgbColor::new(160, 82, 45)),
+	("silver", RgbColor::new(192, 192, 192)),
+	("skyblue", RgbColor::new(135, 206, 235)),
+	("slateblue", RgbColor::new(106, 90, 205)),
+	("slategray", RgbColor::new(112, 128, 144)),
+	("slategrey", RgbColor::new(112, 128, 144)),
+	("snow", RgbColor::new(255, 250, 250)),
+	("springgreen", RgbColor::new(0, 255, 127)),
+	("steelblue", RgbColor::new(70, 130, 180)),
+	("tan", RgbColor::new(210, 180, 140)),
+	("teal", RgbColor::new(0, 128, 128)),
+	("thistle", RgbColor::new(216, 191, 216)),
+	("tomato", RgbColor::new(255, 99, 71)),
+	("turquoise", RgbColor::new(64, 224, 208)),
+	("violet", RgbColor::new(238, 130, 238)),
+	("wheat", RgbColor::new(245, 222, 179)),
+	("white", RgbColor::new(255, 255, 255)),
+	("whitesmoke", RgbColor::new(245, 245, 245)),
+	("yellow", RgbColor::new(255, 255, 0)),
+	("yellowgreen", RgbColor::new(154, 205, 50)),
+];
+
+/// The names xterm gives to each color in its palette
+pub(crate) const XTERM_NAMES: [&str; 256] = [
+	"Black",
+	"Maroon",
+	"Green",
+	"Olive",
+	"Navy",
+	"Purple",
+	"Teal",
+	"Silver",
+	"Grey",
+	"Red",
+	"Lime",
+	"Yellow",
+	"Blue",
+	"Fuchsia",
+	"Aqua",
+	"White",
+	"Grey0",
+	"NavyBlue",
+	"DarkBlue",
+	"Blue3",
+	"Blue3",
+	"Blue1",
+	"DarkGreen",
+	"DeepSkyBlue4",
+	"DeepSkyBlue4",
+	"DeepSkyBlue4",
+	"DodgerBlue3",
+	"DodgerBlue2",
+	"Green4",
+	"SpringGreen4",
+	"Turquoise4",
+	"DeepSkyBlue3",
+	"DeepSkyBlue3",
+	"DodgerBlue1",
+	"Green3",
+	"SpringGreen3",
+	"DarkCyan",
+	"LightSeaGreen",
+	"DeepSkyBlue2",
+	"DeepSkyBlue1",
+	"Green3",
+	"SpringGreen3",
+	"SpringGreen2",
+	"Cyan3",
+	"DarkTurquoise",
+	"Turquoise2",
+	"Green1",
+	"SpringGreen2",
+	"SpringGreen1",
+	"MediumSpringGreen",
+	"Cyan2",
+	"Cyan1",
+	"DarkRed",
+	"DeepPink4",
+	"Purple4",
+	"Purple4",
+	"Purple3",
+	"BlueViolet",
+	"Orange4",
+	"Grey37",
+	"MediumPurple4",
+	"SlateBlue3",
+	"SlateBlue3",
+	"RoyalBlue1",
+	"Chartreuse4",
+	"DarkSeaGreen4",
+	"PaleTurquoise4",
+	"SteelBlue",
+	"SteelBlue3",
+	"CornflowerBlue",
+	"Chartreuse3",
+	"DarkSeaGreen4",
+	"CadetBlue",
+	"CadetBlue",
+	"SkyBlue3",
+	"SteelBlue1",
+	"Chartreuse3",
+	"PaleGreen3",
+	"SeaGreen3",
+	"Aquamarine3",
+	"MediumTurquoise",
+	"SteelBlue1",
+	"Chartreuse2",
+	"SeaGreen2",
+	"SeaGreen1",
+	"SeaGreen1",
+	"Aquamarine1",
+	"DarkSlateGray2",
+	"DarkRed",
+	"DeepPink4",
+	"DarkMagenta",
+	"DarkMagenta",
+	"DarkViolet",
+	"Purple",
+	"Orange4",
+	"LightPink4",
+	"Plum4",
+	"MediumPurple3",
+	"MediumPurple3",
+	"SlateBlue1",
+	"Yellow4",
+	"Wheat4",
+	"Grey53",
+	"LightSlateGrey",
+	"MediumPurple",
+	"LightSlateBlue",
+	"Yellow4",
+	"DarkOliveGreen3",
+	"DarkSeaGreen",
+	"LightSkyBlue3",
+	"LightSkyBlue3",
+	"SkyBlue2",
+	"Chartreuse2",
+	"DarkOliveGreen3",
+	"PaleGreen3",
+	"DarkSeaGreen3",
+	"DarkSlateGray3",
+	"SkyBlue1",
+	"Chartreuse1",
+	"LightGreen",
+	"LightGreen",
+	"PaleGreen1",
+	"Aquamarine1",
+	"DarkSlateGray1",
+	"Red3",
+	"DeepPink4",
+	"MediumVioletRed",
+	"Magenta3",
+	"DarkViolet",
+	"Purple",
+	"DarkOrange3",
+	"IndianRed",
+	"HotPink3",
+	"MediumOrchid3",
+	"MediumOrchid",
+	"MediumPurple2",
+	"DarkGoldenrod",
+	"LightSalmon3",
+	"RosyBrown",
+	"Grey63",
+	"MediumPurple2",
+	"MediumPurple1",
+	"Gold3",
+	"DarkKhaki",
+	"NavajoWhite3",
+	"Grey69",
+	"LightSteelBlue3",
+	"LightSteelBlue",
+	"Yellow3",
+	"DarkOliveGreen3",
+	"DarkSeaGreen3",
+	"DarkSeaGreen2",
+	"LightCyan3",
+	"LightSkyBlue1",
+	"GreenYellow",
+	"DarkOliveGreen2",
+	"PaleGreen1",
+	"DarkSeaGreen2",
+	"DarkSeaGreen1",
+	"PaleTurquoise1",
+	"Red3",
+	"DeepPink3",
+	"DeepPink3",
+	"Magenta3",
+	"Magenta3",
+	"Magenta2",
+	"DarkOrange3",
+	"IndianRed",
+	"HotPink3",
+	"HotPink2",
+	"Orchid",
+	"MediumOrchid1",
+	"Orange3",
+	"LightSalmon3",
+	"LightPink3",
+	"Pink3",
+	"Plum3",
+	"Violet",
+	"Gold3",
+	"LightGoldenrod3",
+	"Tan",
+	"MistyRose3",
+	"Thistle3",
+	"Plum2",
+	"Yellow3",
+	"Khaki3",
+	"LightGoldenrod2",
+	"LightYellow3",
+	"Grey84",
+	"LightSteelBlue1",
+	"Yellow2",
+	"DarkOliveGreen1",
+	"DarkOliveGreen1",
+	"DarkSeaGreen1",
+	"Honeydew2",
+	"LightCyan1",
+	"Red1",
+	"DeepPink2",
+	"DeepPink1",
+	"DeepPink1",
+	"Magenta2",
+	"Magenta1",
+	"OrangeRed1",
+	"IndianRed1",
+	"IndianRed1",
+	"HotPink",
+	"HotPink",
+	"MediumOrchid1",
+	"DarkOrange",
+	"Salmon1",
+	"LightCoral",
+	"PaleVioletRed1",
+	"Orchid2",
+	"Orchid1",
+	"Orange1",
+	"SandyBrown",
+	"LightSalmon1",
+	"LightPink1",
+	"Pink1",
+	"Plum1",
+	"Gold1",
+	"LightGoldenrod2",
+	"LightGoldenrod2",
+	"NavajoWhite1",
+	"MistyRose1",
+	"Thistle1",
+	"Yellow1",
+	"LightGoldenrod1",
+	"Khaki1",
+	"Wheat1",
+	"Cornsilk1",
+	"Grey100",
+	"Grey3",
+	"Grey7",
+	"Grey11",
+	"Grey15",
+	"Grey19",
+	"Grey23",
+	"Grey27",
+	"Grey30",
+	"Grey35",
+	"Grey39",
+	"Grey42",
+	"Grey46",
+	"Grey50",
+	"Grey54",
+	"Grey58",
+	"Grey62",
+	"Grey66",
+	"Grey70",
+	"Grey74",
+	"Grey78",
+	"Grey82",
+	"Grey85",
+	"Grey89",
+	"Grey93",
+];
+
+/// Parses a hex color, without the `#`
+fn parse_hex(hex: &str) -> Option<RgbColor> {
+	if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return None;
+	}
+
+	let channel = |i: usize, len: usize| {
+		let value = u8::from_str_radix(&hex[i * len..(i + 1) * len], 16);
+		value.ok().map(|v| if len == 1 { v * 17 } else { v })
+	};
+	let len = match hex.len() {
+		3 => 1,
+		6 => 2,
+		_ => return None,
+	};
+
+	Some(RgbColor::new(
+		channel(0, len)?,
+		channel(1, len)?,
+		channel(2, len)?,
+	))
+}
+
+/// Parses a channel of an `rgb()` color, which may be a percentage
+fn parse_channel(channel: &str) -> Result<u8, ColorParseErrorKind> {
+	let (value, scale) = match channel.strip_suffix('%') {
+		Some(percent) => (percent, 2.55),
+		None => (channel, 1.0),
+	};
+	let value: f32 = value
+		.parse()
+		.map_err(|_| ColorParseErrorKind::InvalidFunction)?;
+	let value = (value * scale).round();
+
+	if (0.0..=255.0).contains(&value) {
+		Ok(value as u8)
+	} else {
+		Err(ColorParseErrorKind::OutOfRange)
+	}
+}
+
+/// Checks the alpha of an `rgb()` color, which may be a percentage
+fn parse_alpha(alpha: &str) -> Result<(), ColorParseErrorKind> {
+	let (value, max) = match alpha.strip_suffix('%') {
+		Some(percent) => (percent, 100.0),
+		None => (alpha, 1.0),
+	};
+	let value: f32 = value
+		.parse()
+		.map_err(|_| ColorParseErrorKind::InvalidFunction)?;
+
+	if (0.0..=max).contains(&value) {
+		Ok(())
+	} else {
+		Err(ColorParseErrorKind::OutOfRange)
+	}
+}
+
+/// Parses the arguments of an `rgb()` color. Terminals can't show
+/// transparency, so the alpha is checked and then ignored.
+fn parse_function(args: &str) -> Result<RgbColor, ColorParseErrorKind> {
+	let channels: Vec<&str> = args
+		.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+		.filter(|s| !s.is_empty())
+		.collect();
+	let (r, g, b) = match channels[..] {
+		[r, g, b] => (r, g, b),
+		[r, g, b, alpha] => {
+			parse_alpha(alpha)?;
+			(r, g, b)
+		}
+		_ => return Err(ColorParseErrorKind::InvalidFunction),
+	};
+
+	Ok(RgbColor::new(
+		parse_channel(r)?,
+		parse_channel(g)?,
+		parse_channel(b)?,
+	))
+}
+
+/// Parses a hex, `rgb()`, or CSS named color
+pub(crate) fn parse_rgb(input: &str) -> Result<RgbColor, ColorParseError> {
+	let error = |kind| ColorParseError::new(kind, input);
+	let color = input.trim();
+	if color.is_empty() {
+		return Err(error(ColorParseErrorKind::Empty));
+	}
+
+	if let Some(hex) = color.strip_prefix('#') {
+		return parse_hex(hex).ok_or(error(ColorParseErrorKind::InvalidHex));
+	}
+
+	let lower = color.to_ascii_lowercase();
+	let function = lower
+		.strip_prefix("rgb(")
+		.or_else(|| lower.strip_prefix("rgba("));
+	if let Some(args) = function {
+		let args = args
+			.strip_suffix(')')
+			.ok_or(error(ColorParseErrorKind::InvalidFunction))?;
+		return parse_function(args).map_err(error);
+	}
+
+	CSS_COLORS
+		.iter()
+		.find(|(name, _)| *name == lower)
+		.map(|(_, color)| *color)
+		.ok_or(error(ColorParseErrorKind::UnknownName))
+}
+
+/// Parses a palette index or an xterm color name
+pub(crate) fn parse_ansi(input: &str) -> Result<AnsiColor, ColorParseError> {
+	let error = |kind| ColorParseError::new(kind, input);
+	let color = input.trim();
+	if color.is_empty() {
+		return Err(error(ColorParseErrorKind::Empty));
+	}
+
+	if color.bytes().all(|b| b.is_ascii_digit()) {
+		return color
+			.parse()
+			.map(AnsiColor::from_num)
+			.map_err(|_| error(ColorParseErrorKind::OutOfRange));
+	}
+
+	// xterm spells gray both ways, so either spelling is accepted
+	let normalize =
+		|name: &str| name.to_ascii_lowercase().replace("gray", "grey");
+	let name = normalize(color);
+	XTERM_NAMES
+		.iter()
+		.position(|xterm| normalize(xterm) == name)
+		.map(|index| AnsiColor::from_num(index as u8))
+		.ok_or(error(ColorParseErrorKind::UnknownName))
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn parses_rgb() {
+		let orange = RgbColor::new(255, 136, 0);
+		assert_eq!("#ff8800".parse(), Ok(orange));
+		assert_eq!("#F80".parse(), Ok(orange));
+		assert_eq!("rgb(255 136 0)".parse(), Ok(orange));
+		assert_eq!("rgb(100%, 53.3%, 0%)".parse(), Ok(orange));
+		assert_eq!("rgba(255, 136, 0, 0.5)".parse(), Ok(orange));
+		assert_eq!("rgb(255 136 0 / 50%)".parse(), Ok(orange));
+		assert_eq!("RebeccaPurple".parse(), Ok(RgbColor::new(102, 51, 153)));
+
+		let kind = |s: &str| s.parse::<RgbColor>().unwrap_err().kind();
+		assert_eq!(kind("#ff88"), ColorParseErrorKind::InvalidHex);
+		assert_eq!(kind("rgb(1 2)"), ColorParseErrorKind::InvalidFunction);
+		assert_eq!(kind("rgb(1 2 300)"), ColorParseErrorKind::OutOfRange);
+		assert_eq!(kind("rgba(1 2 3 4)"), ColorParseErrorKind::OutOfRange);
+		assert_eq!(kind("rgba(1 2 3 a)"), ColorParseErrorKind::InvalidFunction);
+		assert_eq!(
+			kind("rgba(1 2 3 4 5)"),
+			ColorParseErrorKind::InvalidFunction
+		);
+		assert_eq!(kind("blurple"), ColorParseErrorKind::UnknownName);
+	}
+
+	#[test]
+	fn parses_ansi() {
+		assert_eq!("208".parse(), Ok(AnsiColor::from_num(208)));
+		assert_eq!("DarkOrange3".parse(), Ok(AnsiColor::from_num(130)));
+		assert_eq!("gray50".parse(), Ok(AnsiColor::from_num(244)));
+		assert!("256".parse::<AnsiColor>().is_err());
+	}
+
+	#[test]
+	fn round_trips_names() {
+		for (name, color) in CSS_COLORS {
+			assert_eq!(color.nearest_css_name().parse(), Ok(color), "{}", name);
+			assert_eq!(format!("{:#x}", color).parse(), Ok(color));
+		}
+
+		for index in 0..=255 {
+			let color = AnsiColor::from_num(index);
+			let parsed: AnsiColor = color.xterm_name().parse().unwrap();
+			assert_eq!(parsed.xterm_name(), color.xterm_name());
+		}
+	}
+}
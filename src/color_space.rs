@@ -0,0 +1,571 @@
+use crate::RgbColor;
+
+/// A difference in OKLab which is just barely noticeable
+const JUST_NOTICEABLE: f32 = 0.02;
+/// How precisely the chroma is found when mapping a color into gamut
+const CHROMA_EPSILON: f32 = 0.0001;
+
+/// Wraps a hue into the range `[0, 360)`
+fn normalize_hue(hue: f32) -> f32 {
+	let hue = hue % 360.0;
+	if hue < 0.0 {
+		hue + 360.0
+	} else {
+		hue
+	}
+}
+
+/// Converts a channel from `[0, 1]` into a byte
+fn to_byte(channel: f32) -> u8 {
+	(channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn to_linear(channel: u8) -> f32 {
+	let channel = channel as f32 / 255.0;
+	if channel <= 0.04045 {
+		channel / 12.92
+	} else {
+		((channel + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn from_linear(channel: f32) -> f32 {
+	if channel <= 0.0031308 {
+		channel * 12.92
+	} else {
+		1.055 * channel.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+impl RgbColor {
+	/// The channels of the color with the sRGB transfer function removed
+	pub(crate) fn to_linear(self) -> [f32; 3] {
+		[
+			to_linear(self.get_red()),
+			to_linear(self.get_green()),
+			to_linear(self.get_blue()),
+		]
+	}
+
+	/// Creates a color from linear channels, clamping them into `[0, 1]`
+	pub(crate) fn from_linear([r, g, b]: [f32; 3]) -> Self {
+		RgbColor::new(
+			to_byte(from_linear(r)),
+			to_byte(from_linear(g)),
+			to_byte(from_linear(b)),
+		)
+	}
+
+	/// The perceptual difference between two colors, as the distance between
+	/// them in OKLab. Colors which differ by less than about 0.02 look the
+	/// same.
+	pub fn delta_e_ok(self, other: RgbColor) -> f32 {
+		Oklab::from(self).delta_e(Oklab::from(other))
+	}
+
+	/// The perceptual difference between two colors, using the CIEDE2000
+	/// formula. Colors which differ by less than about 1 look the same.
+	pub fn delta_e_2000(self, other: RgbColor) -> f32 {
+		Lab::from(self).delta_e_2000(Lab::from(other))
+	}
+}
+
+/// A color given by its hue, saturation, and lightness
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Hsl {
+	/// The hue, in degrees
+	pub hue: f32,
+	/// The saturation, from 0 to 1
+	pub saturation: f32,
+	/// The lightness, from 0 to 1
+	pub lightness: f32,
+}
+
+impl Hsl {
+	pub const fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+		Hsl {
+			hue,
+			saturation,
+			lightness,
+		}
+	}
+}
+
+/// The hue, and the smallest and largest channels, of an RGB color
+fn hue_and_range(color: RgbColor) -> (f32, f32, f32) {
+	let r = color.get_red() as f32 / 255.0;
+	let g = color.get_green() as f32 / 255.0;
+	let b = color.get_blue() as f32 / 255.0;
+	let max = r.max(g).max(b);
+	let min = r.min(g).min(b);
+	let delta = max - min;
+
+	let hue = if delta == 0.0 {
+		0.0
+	} else if max == r {
+		60.0 * ((g - b) / delta)
+	} else if max == g {
+		60.0 * ((b - r) / delta + 2.0)
+	} else {
+		60.0 * ((r - g) / delta + 4.0)
+	};
+
+	(normalize_hue(hue), min, max)
+}
+
+impl From<RgbColor> for Hsl {
+	fn from(color: RgbColor) -> Self {
+		let (hue, min, max) = hue_and_range(color);
+		let lightness = (max + min) / 2.0;
+		let saturation = if max == min {
+			0.0
+		} else {
+			(max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+		};
+
+		Hsl::new(hue, saturation, lightness)
+	}
+}
+
+impl From<Hsl> for RgbColor {
+	fn from(hsl: Hsl) -> Self {
+		let saturation = hsl.saturation.clamp(0.0, 1.0);
+		let lightness = hsl.lightness.clamp(0.0, 1.0);
+		let hue = normalize_hue(hsl.hue);
+
+		let channel = |n: f32| {
+			let k = (n + hue / 30.0) % 12.0;
+			let a = saturation * lightness.min(1.0 - lightness);
+			to_byte(lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0))
+		};
+
+		RgbColor::new(channel(0.0), channel(8.0), channel(4.0))
+	}
+}
+
+/// A color given by its hue, saturation, and value
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Hsv {
+	/// The hue, in degrees
+	pub hue: f32,
+	/// The saturation, from 0 to 1
+	pub saturation: f32,
+	/// The value, from 0 to 1
+	pub value: f32,
+}
+
+impl Hsv {
+	pub const fn new(hue: f32, saturation: f32, value: f32) -> Self {
+		Hsv {
+			hue,
+			saturation,
+			value,
+		}
+	}
+}
+
+impl From<RgbColor> for Hsv {
+	fn from(color: RgbColor) -> Self {
+		let (hue, min, max) = hue_and_range(color);
+		let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+		Hsv::new(hue, saturation, max)
+	}
+}
+
+impl From<Hsv> for RgbColor {
+	fn from(hsv: Hsv) -> Self {
+		let saturation = hsv.saturation.clamp(0.0, 1.0);
+		let value = hsv.value.clamp(0.0, 1.0);
+		let hue = normalize_hue(hsv.hue);
+
+		let channel = |n: f32| {
+			let k = (n + hue / 60.0) % 6.0;
+			let amount = k.min(4.0 - k).clamp(0.0, 1.0);
+			to_byte(value - value * saturation * amount)
+		};
+
+		RgbColor::new(channel(5.0), channel(3.0), channel(1.0))
+	}
+}
+
+/// A color given by its hue, whiteness, and blackness
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Hwb {
+	/// The hue, in degrees
+	pub hue: f32,
+	/// The amount of white, from 0 to 1
+	pub whiteness: f32,
+	/// The amount of black, from 0 to 1
+	pub blackness: f32,
+}
+
+impl Hwb {
+	pub const fn new(hue: f32, whiteness: f32, blackness: f32) -> Self {
+		Hwb {
+			hue,
+			whiteness,
+			blackness,
+		}
+	}
+}
+
+impl From<RgbColor> for Hwb {
+	fn from(color: RgbColor) -> Self {
+		let hsv = Hsv::from(color);
+		let whiteness = (1.0 - hsv.saturation) * hsv.value;
+		Hwb::new(hsv.hue, whiteness, 1.0 - hsv.value)
+	}
+}
+
+impl From<Hwb> for RgbColor {
+	fn from(hwb: Hwb) -> Self {
+		let whiteness = hwb.whiteness.clamp(0.0, 1.0);
+		let blackness = hwb.blackness.clamp(0.0, 1.0);
+		if whiteness + blackness >= 1.0 {
+			let gray = to_byte(whiteness / (whiteness + blackness));
+			return RgbColor::new(gray, gray, gray);
+		}
+
+		let value = 1.0 - blackness;
+		let saturation = 1.0 - whiteness / value;
+		Hsv::new(hwb.hue, saturation, value).into()
+	}
+}
+
+/// A color in the OKLab perceptual color space
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Oklab {
+	/// The perceived lightness, from 0 to 1
+	pub l: f32,
+	/// How green (negative) or red (positive) the color is
+	pub a: f32,
+	/// How blue (negative) or yellow (positive) the color is
+	pub b: f32,
+}
+
+impl Oklab {
+	pub const fn new(l: f32, a: f32, b: f32) -> Self {
+		Oklab { l, a, b }
+	}
+
+	/// The euclidean distance between two colors
+	pub fn delta_e(self, other: Oklab) -> f32 {
+		let l = self.l - other.l;
+		let a = self.a - other.a;
+		let b = self.b - other.b;
+		(l * l + a * a + b * b).sqrt()
+	}
+
+	pub(crate) fn from_linear([r, g, b]: [f32; 3]) -> Self {
+		let l = 0.41222146 * r + 0.53633255 * g + 0.051445995 * b;
+		let m = 0.2119035 * r + 0.6806995 * g + 0.10739696 * b;
+		let s = 0.08830246 * r + 0.28171885 * g + 0.6299787 * b;
+		let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+
+		Oklab::new(
+			0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+			1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+			0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+		)
+	}
+
+	/// Converts the color to linear sRGB, which may be out of gamut
+	pub(crate) fn to_linear(self) -> [f32; 3] {
+		let l = self.l + 0.39633778 * self.a + 0.21580376 * self.b;
+		let m = self.l - 0.105561346 * self.a - 0.06385417 * self.b;
+		let s = self.l - 0.08948418 * self.a - 1.2914855 * self.b;
+		let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+
+		[
+			4.0767417 * l - 3.3077116 * m + 0.23096994 * s,
+			-1.268438 * l + 2.6097574 * m - 0.34131938 * s,
+			-0.0041960864 * l - 0.7034186 * m + 1.7076147 * s,
+		]
+	}
+}
+
+impl From<RgbColor> for Oklab {
+	fn from(color: RgbColor) -> Self {
+		Oklab::from_linear(color.to_linear())
+	}
+}
+
+/// Converts the color to sRGB, mapping it into gamut the way CSS does, by
+/// reducing its chroma until clipping it is no longer noticeable
+impl From<Oklab> for RgbColor {
+	fn from(lab: Oklab) -> Self {
+		Oklch::from(lab).into()
+	}
+}
+
+/// A color in the cylindrical form of OKLab
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Oklch {
+	/// The perceived lightness, from 0 to 1
+	pub l: f32,
+	/// The chroma, from 0 to about 0.37
+	pub c: f32,
+	/// The hue, in degrees
+	pub h: f32,
+}
+
+impl Oklch {
+	pub const fn new(l: f32, c: f32, h: f32) -> Self {
+		Oklch { l, c, h }
+	}
+}
+
+impl From<Oklab> for Oklch {
+	fn from(lab: Oklab) -> Self {
+		let c = (lab.a * lab.a + lab.b * lab.b).sqrt();
+		let h = normalize_hue(lab.b.atan2(lab.a).to_degrees());
+		Oklch::new(lab.l, c, h)
+	}
+}
+
+impl From<Oklch> for Oklab {
+	fn from(lch: Oklch) -> Self {
+		let (sin, cos) = lch.h.to_radians().sin_cos();
+		Oklab::new(lch.l, lch.c * cos, lch.c * sin)
+	}
+}
+
+impl From<RgbColor> for Oklch {
+	fn from(color: RgbColor) -> Self {
+		Oklab::from(color).into()
+	}
+}
+
+fn in_gamut(linear: [f32; 3]) -> bool {
+	const EPSILON: f32 = 0.00001;
+	linear
+		.iter()
+		.all(|&channel| (-EPSILON..=1.0 + EPSILON).contains(&channel))
+}
+
+fn clip(linear: [f32; 3]) -> [f32; 3] {
+	linear.map(|channel| channel.clamp(0.0, 1.0))
+}
+
+/// Converts the color to sRGB, mapping it into gamut the way CSS does, by
+/// reducing its chroma until clipping it is no longer noticeable
+impl From<Oklch> for RgbColor {
+	fn from(lch: Oklch) -> Self {
+		if lch.l >= 1.0 {
+			return RgbColor::new(255, 255, 255);
+		}
+		if lch.l <= 0.0 {
+			return RgbColor::new(0, 0, 0);
+		}
+
+		let original = Oklab::from(lch).to_linear();
+		if in_gamut(original) {
+			return RgbColor::from_linear(original);
+		}
+
+		let difference = |linear: [f32; 3], clipped: [f32; 3]| {
+			Oklab::from_linear(linear).delta_e(Oklab::from_linear(clipped))
+		};
+
+		let mut clipped = clip(original);
+		if difference(original, clipped) < JUST_NOTICEABLE {
+			return RgbColor::from_linear(clipped);
+		}
+
+		let mut min = 0.0;
+		let mut max = lch.c.max(0.0);
+		let mut min_in_gamut = true;
+		while max - min > CHROMA_EPSILON {
+			let chroma = (min + max) / 2.0;
+			let current = Oklab::from(Oklch::new(lch.l, chroma, lch.h));
+			let current = current.to_linear();
+
+			if min_in_gamut && in_gamut(current) {
+				min = chroma;
+				continue;
+			}
+
+			clipped = clip(current);
+			let error = difference(current, clipped);
+			if error < JUST_NOTICEABLE {
+				if JUST_NOTICEABLE - error < CHROMA_EPSILON {
+					break;
+				}
+				min_in_gamut = false;
+				min = chroma;
+			} else {
+				max = chroma;
+			}
+		}
+
+		RgbColor::from_linear(clipped)
+	}
+}
+
+/// A color in the CIELAB color space, relative to a D65 white point
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Lab {
+	/// The lightness, from 0 to 100
+	pub l: f32,
+	/// How green (negative) or red (positive) the color is
+	pub a: f32,
+	/// How blue (negative) or yellow (positive) the color is
+	pub b: f32,
+}
+
+impl Lab {
+	pub const fn new(l: f32, a: f32, b: f32) -> Self {
+		Lab { l, a, b }
+	}
+
+	/// The difference between two colors, using the CIEDE2000 formula
+	pub fn delta_e_2000(self, other: Lab) -> f32 {
+		let pow7 = |x: f32| x.powi(7);
+		let twenty_five_pow7 = pow7(25.0);
+		let hue = |b: f32, a: f32| {
+			if a == 0.0 && b == 0.0 {
+				0.0
+			} else {
+				normalize_hue(b.atan2(a).to_degrees())
+			}
+		};
+
+		let c1 = self.a.hypot(self.b);
+		let c2 = other.a.hypot(other.b);
+		let c_mean = (c1 + c2) / 2.0;
+		let g = 0.5
+			* (1.0 - (pow7(c_mean) / (pow7(c_mean) + twenty_five_pow7)).sqrt());
+
+		let a1 = (1.0 + g) * self.a;
+		let a2 = (1.0 + g) * other.a;
+		let c1 = a1.hypot(self.b);
+		let c2 = a2.hypot(other.b);
+		let h1 = hue(self.b, a1);
+		let h2 = hue(other.b, a2);
+
+		let delta_l = other.l - self.l;
+		let delta_c = c2 - c1;
+		let delta_h = if c1 * c2 == 0.0 {
+			0.0
+		} else if (h2 - h1).abs() <= 180.0 {
+			h2 - h1
+		} else if h2 - h1 > 180.0 {
+			h2 - h1 - 360.0
+		} else {
+			h2 - h1 + 360.0
+		};
+		let delta_h =
+			2.0 * (c1 * c2).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+		let l_mean = (self.l + other.l) / 2.0;
+		let c_mean = (c1 + c2) / 2.0;
+		let h_mean = if c1 * c2 == 0.0 {
+			h1 + h2
+		} else if (h1 - h2).abs() <= 180.0 {
+			(h1 + h2) / 2.0
+		} else if h1 + h2 < 360.0 {
+			(h1 + h2 + 360.0) / 2.0
+		} else {
+			(h1 + h2 - 360.0) / 2.0
+		};
+
+		let cos = |degrees: f32| degrees.to_radians().cos();
+		let t = 1.0 - 0.17 * cos(h_mean - 30.0)
+			+ 0.24 * cos(2.0 * h_mean)
+			+ 0.32 * cos(3.0 * h_mean + 6.0)
+			- 0.20 * cos(4.0 * h_mean - 63.0);
+		let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+		let r_c =
+			2.0 * (pow7(c_mean) / (pow7(c_mean) + twenty_five_pow7)).sqrt();
+		let l_offset = (l_mean - 50.0).powi(2);
+		let s_l = 1.0 + 0.015 * l_offset / (20.0 + l_offset).sqrt();
+		let s_c = 1.0 + 0.045 * c_mean;
+		let s_h = 1.0 + 0.015 * c_mean * t;
+		let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+		let l = delta_l / s_l;
+		let c = delta_c / s_c;
+		let h = delta_h / s_h;
+		(l * l + c * c + h * h + r_t * c * h).sqrt()
+	}
+}
+
+impl From<RgbColor> for Lab {
+	fn from(color: RgbColor) -> Self {
+		let [r, g, b] = color.to_linear();
+		let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+		let y = 0.2126729 * r + 0.7151522 * g + 0.072175 * b;
+		let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+		let f = |t: f32| {
+			const DELTA: f32 = 6.0 / 29.0;
+			if t > DELTA * DELTA * DELTA {
+				t.cbrt()
+			} else {
+				t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+			}
+		};
+
+		Lab::new(
+			116.0 * f(y) - 16.0,
+			500.0 * (f(x) - f(y)),
+			200.0 * (f(y) - f(z)),
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn round_trips() {
+		let colors = [
+			RgbColor::new(255, 136, 0),
+			RgbColor::new(102, 51, 153),
+			RgbColor::new(0, 0, 0),
+			RgbColor::new(200, 200, 200),
+		];
+
+		for color in colors {
+			assert_eq!(RgbColor::from(Hsl::from(color)), color);
+			assert_eq!(RgbColor::from(Hsv::from(color)), color);
+			assert_eq!(RgbColor::from(Hwb::from(color)), color);
+			assert_eq!(RgbColor::from(Oklch::from(color)), color);
+		}
+
+		let hsl = Hsl::from(RgbColor::new(255, 136, 0));
+		assert_eq!(hsl.hue.round(), 32.0);
+	}
+
+	#[test]
+	fn gamut_mapping() {
+		// far outside of sRGB, so the chroma has to be reduced
+		let color = RgbColor::from(Oklch::new(0.7, 0.4, 150.0));
+		let lch = Oklch::from(color);
+		assert!((lch.l - 0.7).abs() < 0.02);
+		assert!((lch.h - 150.0).abs() < 5.0);
+		assert!(lch.c < 0.25);
+	}
+
+	#[test]
+	fn ciede2000() {
+		// from the test data published by Sharma, Wu and Dalal
+		let pairs = [
+			((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+			((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+			((50.0, 2.5, 0.0), (50.0, 3.2592, 0.335), 1.0),
+			(
+				(60.2574, -34.0099, 36.2677),
+				(60.4626, -34.1751, 39.4387),
+				1.2644,
+			),
+		];
+
+		for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+			let delta = Lab::new(l1, a1, b1).delta_e_2000(Lab::new(l2, a2, b2));
+			assert!((delta - expected).abs() < 0.001, "{}", delta);
+		}
+	}
+}
@@ -0,0 +1,295 @@
+use crate::{Chalk, ChalkType, Oklch, Palette, RgbColor};
+
+/// The coefficients of each channel in APCA's luminance
+const APCA_COEFFICIENTS: [f32; 3] = [0.2126729, 0.7151522, 0.072175];
+/// Luminance below this is raised, since dark colors are hard to tell apart
+const APCA_BLACK_THRESHOLD: f32 = 0.022;
+/// Contrast below this is treated as no contrast at all
+const APCA_LOW_CLIP: f32 = 0.1;
+
+/// How many steps are taken when searching for a readable lightness
+const SEARCH_STEPS: usize = 16;
+
+impl RgbColor {
+	/// The relative luminance of the color, as defined by WCAG 2.x. This is
+	/// 0 for black and 1 for white.
+	pub fn relative_luminance(self) -> f32 {
+		let [r, g, b] = self.to_linear();
+		0.2126 * r + 0.7152 * g + 0.0722 * b
+	}
+
+	/// The WCAG 2.x contrast ratio between two colors, from 1 to 21. The
+	/// order of the colors doesn't matter. Text should have a ratio of at
+	/// least 4.5, or 3 if it's large.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::RgbColor;
+	///
+	/// let black = RgbColor::new(0, 0, 0);
+	/// let white = RgbColor::new(255, 255, 255);
+	/// assert_eq!(black.contrast_ratio(white).round(), 21.0);
+	/// ```
+	pub fn contrast_ratio(self, other: RgbColor) -> f32 {
+		let a = self.relative_luminance() + 0.05;
+		let b = other.relative_luminance() + 0.05;
+		a.max(b) / a.min(b)
+	}
+
+	/// The luminance of the color as APCA sees it
+	fn apca_luminance(self) -> f32 {
+		let channels = [self.get_red(), self.get_green(), self.get_blue()];
+		let y: f32 = channels
+			.iter()
+			.zip(APCA_COEFFICIENTS.iter())
+			.map(|(&c, k)| (c as f32 / 255.0).powf(2.4) * k)
+			.sum();
+
+		if y < APCA_BLACK_THRESHOLD {
+			y + (APCA_BLACK_THRESHOLD - y).powf(1.414)
+		} else {
+			y
+		}
+	}
+
+	/// The APCA lightness contrast (Lc) of text in this color on a
+	/// background. This is positive for dark text on a light background and
+	/// negative for light text on a dark background. Body text should have a
+	/// contrast of at least 75 either way.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::RgbColor;
+	///
+	/// let black = RgbColor::new(0, 0, 0);
+	/// let white = RgbColor::new(255, 255, 255);
+	/// assert_eq!(black.apca_contrast(white).round(), 106.0);
+	/// assert_eq!(white.apca_contrast(black).round(), -108.0);
+	/// ```
+	pub fn apca_contrast(self, background: RgbColor) -> f32 {
+		let text = self.apca_luminance();
+		let background = background.apca_luminance();
+		if (background - text).abs() < 0.0005 {
+			return 0.0;
+		}
+
+		let contrast = if background > text {
+			let sapc = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+			if sapc < APCA_LOW_CLIP {
+				0.0
+			} else {
+				sapc - 0.027
+			}
+		} else {
+			let sapc = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+			if sapc > -APCA_LOW_CLIP {
+				0.0
+			} else {
+				sapc + 0.027
+			}
+		};
+
+		contrast * 100.0
+	}
+}
+
+/// Changes the lightness of a color as little as possible, so that its
+/// contrast with a background is at least the given ratio. The hue and
+/// chroma are kept, apart from any changes needed to stay in gamut.
+fn readable_color(
+	text: RgbColor,
+	background: RgbColor,
+	min_ratio: f32,
+) -> RgbColor {
+	let black = RgbColor::new(0, 0, 0);
+	let white = RgbColor::new(255, 255, 255);
+	let darken =
+		black.contrast_ratio(background) > white.contrast_ratio(background);
+
+	let original = Oklch::from(text);
+	let with_lightness =
+		|l| RgbColor::from(Oklch::new(l, original.c, original.h));
+	let (mut passes, mut fails) = if darken {
+		(0.0, original.l)
+	} else {
+		(1.0, original.l)
+	};
+	if with_lightness(passes).contrast_ratio(background) < min_ratio {
+		return if darken { black } else { white };
+	}
+
+	for _ in 0..SEARCH_STEPS {
+		let middle = (passes + fails) / 2.0;
+		if with_lightness(middle).contrast_ratio(background) >= min_ratio {
+			passes = middle;
+		} else {
+			fails = middle;
+		}
+	}
+
+	with_lightness(passes)
+}
+
+/** Contrast */
+impl Chalk {
+	/// The colors the text and its background are shown in, using the
+	/// palette for basic and ANSI colors and for colors which aren't set.
+	/// Inverted colors are swapped.
+	fn shown_colors(&self, palette: &Palette) -> (RgbColor, RgbColor) {
+		let foreground = palette
+			.resolve(&self.foreground)
+			.unwrap_or_else(|| palette.foreground());
+		let background = palette
+			.resolve(&self.background)
+			.unwrap_or_else(|| palette.background());
+
+		if self.is_inverted() {
+			(background, foreground)
+		} else {
+			(foreground, background)
+		}
+	}
+
+	/// The color which the text is shown in
+	fn text_color_mut(&mut self) -> &mut ChalkType {
+		if self.is_inverted() {
+			&mut self.background
+		} else {
+			&mut self.foreground
+		}
+	}
+
+	/// The WCAG 2.x contrast ratio between the text and its background, from
+	/// 1 to 21. Basic and ANSI colors are looked up in the palette, such as
+	/// [`Palette::detect`].
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, Palette};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.light_yellow().bg_white();
+	/// assert!(chalk.contrast_ratio(&Palette::xterm()) < 1.1);
+	/// ```
+	pub fn contrast_ratio(&self, palette: &Palette) -> f32 {
+		let (text, background) = self.shown_colors(palette);
+		text.contrast_ratio(background)
+	}
+
+	/// The APCA lightness contrast between the text and its background. See
+	/// [`RgbColor::apca_contrast`].
+	pub fn apca_contrast(&self, palette: &Palette) -> f32 {
+		let (text, background) = self.shown_colors(palette);
+		text.apca_contrast(background)
+	}
+
+	/// Changes the lightness of the text's color until its contrast ratio
+	/// with the background is at least `min_ratio`.
+	///
+	/// The color is made darker on light backgrounds and lighter on dark
+	/// ones, keeping its hue. Nothing changes if the contrast is already high
+	/// enough. Otherwise, the text's color becomes an RGB color, so it may be
+	/// less accurate when the terminal doesn't support RGB colors. Ratios
+	/// above 21 can't be reached, so the text becomes black or white.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, Palette};
+	///
+	/// let palette = Palette::xterm();
+	/// let mut chalk = Chalk::new();
+	/// chalk.light_yellow().bg_white().ensure_contrast(4.5, &palette);
+	/// assert!(chalk.contrast_ratio(&palette) >= 4.5);
+	/// ```
+	pub fn ensure_contrast(
+		&mut self,
+		min_ratio: f32,
+		palette: &Palette,
+	) -> &mut Self {
+		let (text, background) = self.shown_colors(palette);
+		if text.contrast_ratio(background) < min_ratio {
+			let color = readable_color(text, background, min_ratio);
+			*self.text_color_mut() = ChalkType::Rgb(color);
+		}
+
+		self
+	}
+
+	/// Sets the text to black or white, whichever is easier to read on the
+	/// background.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ChalkType, Palette, RgbColor};
+	///
+	/// let mut badge = Chalk::new();
+	/// badge.bg_light_green().auto_foreground(&Palette::xterm());
+	/// assert_eq!(badge.foreground(), &ChalkType::Rgb(RgbColor::new(0, 0, 0)));
+	/// ```
+	pub fn auto_foreground(&mut self, palette: &Palette) -> &mut Self {
+		let (_, background) = self.shown_colors(palette);
+		let black = RgbColor::new(0, 0, 0);
+		let white = RgbColor::new(255, 255, 255);
+		let color = if black.contrast_ratio(background)
+			>= white.contrast_ratio(background)
+		{
+			black
+		} else {
+			white
+		};
+
+		*self.text_color_mut() = ChalkType::Rgb(color);
+		self
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn contrast() {
+		let gray = RgbColor::new(118, 118, 118);
+		let white = RgbColor::new(255, 255, 255);
+		let ratio = gray.contrast_ratio(white);
+		assert!((ratio - 4.54).abs() < 0.01, "{}", ratio);
+
+		let text = RgbColor::new(136, 136, 136);
+		let apca = text.apca_contrast(white);
+		assert!((apca - 63.06).abs() < 0.1, "{}", apca);
+		assert_eq!(white.apca_contrast(white), 0.0);
+	}
+
+	#[test]
+	fn readable_colors() {
+		let palette = Palette::xterm();
+		let mut chalk = Chalk::new();
+		chalk.light_yellow().bg_white();
+		chalk.ensure_contrast(7.0, &palette);
+		assert!(chalk.contrast_ratio(&palette) >= 7.0);
+		// the color is darkened, but stays yellow
+		let ChalkType::Rgb(color) = chalk.foreground else {
+			panic!("the foreground should be rgb");
+		};
+		assert!(color.get_blue() < color.get_red() / 2);
+
+		let mut inverted = Chalk::new();
+		inverted
+			.invert()
+			.bg_blue()
+			.black()
+			.auto_foreground(&palette);
+		let white = ChalkType::Rgb(RgbColor::new(255, 255, 255));
+		assert_eq!(inverted.background, white);
+
+		let mut impossible = Chalk::new();
+		impossible.red().ensure_contrast(30.0, &palette);
+		assert_eq!(impossible.foreground, white);
+	}
+}
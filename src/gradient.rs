@@ -0,0 +1,380 @@
+use crate::width::graphemes;
+use crate::{
+	visible_width, Chalk, ChalkType, ColorLevel, Hsl, Oklab, RgbColor,
+};
+
+use std::fmt::Write;
+
+/// The color space a gradient blends its colors in
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Interpolation {
+	/// Blends the sRGB channels directly. This is what most programs do, but
+	/// the middle of the gradient tends to look dark and muddy.
+	Srgb,
+	/// Blends the channels with the sRGB transfer function removed, which
+	/// mixes light the way it does physically
+	LinearRgb,
+	/// Blends the hue, saturation, and lightness, taking the shorter way
+	/// around the hue circle
+	Hsl,
+	/// Blends in OKLab, so that the steps look evenly spaced
+	#[default]
+	Oklab,
+}
+
+/// A smooth transition between colors, which can be applied across text.
+///
+/// The colors are spread evenly over the text, one color for each grapheme.
+/// When the terminal doesn't support true color, each color is replaced by
+/// the closest one it does support, and neighboring graphemes which end up
+/// with the same color are written together.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{ColorLevel, Gradient, RgbColor};
+///
+/// let gradient =
+/// 	Gradient::new([RgbColor::new(255, 0, 0), RgbColor::new(0, 0, 255)]);
+/// assert_eq!(gradient.color_at(0.5), RgbColor::new(140, 83, 162));
+///
+/// let text = gradient.apply_at(ColorLevel::TrueColor, "hey");
+/// assert_eq!(
+/// 	text,
+/// 	"\x1b[38;2;255;0;0mh\x1b[39m\x1b[38;2;140;83;162me\x1b[39m\x1b[38;2;0;0;255my\x1b[39m"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Gradient {
+	stops: Vec<RgbColor>,
+	interpolation: Interpolation,
+	chalk: Chalk,
+	background: bool,
+}
+
+/// Linearly interpolates between two numbers
+fn mix(from: f32, to: f32, t: f32) -> f32 {
+	from + (to - from) * t
+}
+
+impl Gradient {
+	/// Creates a gradient going through the given colors, evenly spaced.
+	///
+	/// # Panics
+	///
+	/// Panics if there are no colors.
+	pub fn new(stops: impl IntoIterator<Item = RgbColor>) -> Self {
+		let stops: Vec<_> = stops.into_iter().collect();
+		assert!(!stops.is_empty(), "a gradient needs at least one color");
+
+		Gradient {
+			stops,
+			interpolation: Interpolation::default(),
+			chalk: Chalk::default(),
+			background: false,
+		}
+	}
+
+	/// Creates a gradient going through every hue, starting at the given hue
+	/// in degrees and going all the way around back to it
+	pub fn hue_cycle(start: f32) -> Self {
+		let stops =
+			(0..=6).map(|i| Hsl::new(start + 60.0 * i as f32, 1.0, 0.5).into());
+		let mut gradient = Self::new(stops);
+		gradient.interpolation(Interpolation::Hsl);
+		gradient
+	}
+
+	/// Creates a gradient going through the colors of the rainbow, from red
+	/// to magenta
+	pub fn rainbow() -> Self {
+		let stops = (0..=5).map(|i| Hsl::new(60.0 * i as f32, 1.0, 0.5).into());
+		let mut gradient = Self::new(stops);
+		gradient.interpolation(Interpolation::Hsl);
+		gradient
+	}
+
+	/// Sets the color space the colors are blended in
+	#[inline(always)]
+	pub fn interpolation(&mut self, interpolation: Interpolation) -> &mut Self {
+		self.interpolation = interpolation;
+		self
+	}
+
+	/// Applies the gradient to the background instead of the foreground
+	#[inline(always)]
+	pub fn background(&mut self) -> &mut Self {
+		self.background = true;
+		self
+	}
+
+	/// Applies the gradient to the foreground. This is the default.
+	#[inline(always)]
+	pub fn foreground(&mut self) -> &mut Self {
+		self.background = false;
+		self
+	}
+
+	/// Sets the style the gradient is drawn on top of, such as bold text, or
+	/// a background color for a foreground gradient
+	pub fn base(&mut self, chalk: &Chalk) -> &mut Self {
+		self.chalk = *chalk;
+		self
+	}
+
+	/// The colors the gradient goes through
+	pub fn stops(&self) -> &[RgbColor] {
+		&self.stops
+	}
+
+	/// Blends two colors in the gradient's color space
+	fn blend(&self, from: RgbColor, to: RgbColor, t: f32) -> RgbColor {
+		match self.interpolation {
+			Interpolation::Srgb => {
+				let channel =
+					|a: u8, b: u8| mix(a as f32, b as f32, t).round() as u8;
+				RgbColor::new(
+					channel(from.get_red(), to.get_red()),
+					channel(from.get_green(), to.get_green()),
+					channel(from.get_blue(), to.get_blue()),
+				)
+			}
+			Interpolation::LinearRgb => {
+				let (from, to) = (from.to_linear(), to.to_linear());
+				RgbColor::from_linear([
+					mix(from[0], to[0], t),
+					mix(from[1], to[1], t),
+					mix(from[2], to[2], t),
+				])
+			}
+			Interpolation::Hsl => {
+				let (from, to) = (Hsl::from(from), Hsl::from(to));
+				let mut turn = (to.hue - from.hue) % 360.0;
+				if turn > 180.0 {
+					turn -= 360.0;
+				} else if turn < -180.0 {
+					turn += 360.0;
+				}
+
+				Hsl::new(
+					from.hue + turn * t,
+					mix(from.saturation, to.saturation, t),
+					mix(from.lightness, to.lightness, t),
+				)
+				.into()
+			}
+			Interpolation::Oklab => {
+				let (from, to) = (Oklab::from(from), Oklab::from(to));
+				Oklab::new(
+					mix(from.l, to.l, t),
+					mix(from.a, to.a, t),
+					mix(from.b, to.b, t),
+				)
+				.into()
+			}
+		}
+	}
+
+	/// Gets the color at a point along the gradient, from 0 at the start to 1
+	/// at the end
+	pub fn color_at(&self, t: f32) -> RgbColor {
+		let last = self.stops.len() - 1;
+		let position = t.clamp(0.0, 1.0) * last as f32;
+		let index = (position.floor() as usize).min(last.saturating_sub(1));
+		match self.stops.get(index + 1) {
+			Some(&to) => {
+				self.blend(self.stops[index], to, position - index as f32)
+			}
+			None => self.stops[index],
+		}
+	}
+
+	/// Gets the given number of colors, evenly spaced along the gradient
+	pub fn colors(&self, count: usize) -> Vec<RgbColor> {
+		let steps = count.saturating_sub(1).max(1) as f32;
+		(0..count)
+			.map(|i| self.color_at(i as f32 / steps))
+			.collect()
+	}
+
+	/// Styles each grapheme in the text with the color at its position, which
+	/// is given from its line and column
+	fn render(
+		&self,
+		level: ColorLevel,
+		text: &str,
+		position: impl Fn(usize, usize) -> f32,
+	) -> String {
+		let mut output = String::with_capacity(text.len());
+		for (row, line) in text.split('\n').enumerate() {
+			if row > 0 {
+				output.push('\n');
+			}
+
+			let mut run: Option<(Chalk, String)> = None;
+			let mut column = 0;
+			for grapheme in graphemes(line) {
+				let width = visible_width(grapheme);
+				let color =
+					ChalkType::Rgb(self.color_at(position(row, column)))
+						.at_level(level);
+				column += width;
+
+				// the foreground color doesn't matter for spaces
+				let blank = !self.background && grapheme.trim().is_empty();
+				if let Some((chalk, text)) = &mut run {
+					let same = if self.background {
+						chalk.background == color
+					} else {
+						chalk.foreground == color
+					};
+					if same || blank {
+						text.push_str(grapheme);
+						continue;
+					}
+
+					// writing to a string can't fail
+					let painted = chalk.paint(text.as_str()).at_level(level);
+					let _ = write!(output, "{}", painted);
+				}
+
+				let mut chalk = self.chalk;
+				if self.background {
+					chalk.background = color;
+				} else {
+					chalk.foreground = color;
+				}
+				run = Some((chalk, grapheme.to_string()));
+			}
+
+			if let Some((chalk, text)) = run {
+				let painted = chalk.paint(text).at_level(level);
+				let _ = write!(output, "{}", painted);
+			}
+		}
+
+		output
+	}
+
+	/// Applies the gradient across the text at the given color level
+	pub fn apply_at(&self, level: ColorLevel, text: &str) -> String {
+		let width = visible_width(text).saturating_sub(1).max(1);
+		let lines: Vec<_> = text.split('\n').collect();
+		let offsets: Vec<_> = lines
+			.iter()
+			.scan(0, |offset, line| {
+				let start = *offset;
+				*offset += visible_width(line);
+				Some(start)
+			})
+			.collect();
+
+		self.render(level, text, |row, column| {
+			(offsets[row] + column) as f32 / width as f32
+		})
+	}
+
+	/// Applies the gradient across the text at the color level of standard
+	/// output
+	pub fn apply(&self, text: &str) -> String {
+		self.apply_at(ColorLevel::stdout(), text)
+	}
+
+	/// Applies the gradient across each line of the text at the given color
+	/// level, so that the colors line up in columns
+	pub fn apply_lines_at(&self, level: ColorLevel, text: &str) -> String {
+		let width = text
+			.split('\n')
+			.map(visible_width)
+			.max()
+			.unwrap_or(0)
+			.saturating_sub(1)
+			.max(1);
+
+		self.render(level, text, |_, column| column as f32 / width as f32)
+	}
+
+	/// Applies the gradient across each line of the text at the color level of
+	/// standard output
+	pub fn apply_lines(&self, text: &str) -> String {
+		self.apply_lines_at(ColorLevel::stdout(), text)
+	}
+
+	/// Applies the gradient diagonally across the text at the given color
+	/// level, from the top left corner to the bottom right.
+	///
+	/// Each line moves along the gradient by two columns, since a terminal
+	/// cell is about twice as tall as it is wide.
+	pub fn apply_diagonal_at(&self, level: ColorLevel, text: &str) -> String {
+		let lines = text.split('\n');
+		let rows = lines.clone().count() - 1;
+		let width = lines.map(visible_width).max().unwrap_or(0);
+		let length = (width.saturating_sub(1) + 2 * rows).max(1);
+
+		self.render(level, text, |row, column| {
+			(column + 2 * row) as f32 / length as f32
+		})
+	}
+
+	/// Applies the gradient diagonally across the text at the color level of
+	/// standard output
+	pub fn apply_diagonal(&self, text: &str) -> String {
+		self.apply_diagonal_at(ColorLevel::stdout(), text)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn interpolates() {
+		let black = RgbColor::new(0, 0, 0);
+		let white = RgbColor::new(255, 255, 255);
+		let mut gradient = Gradient::new([black, white]);
+
+		gradient.interpolation(Interpolation::Srgb);
+		assert_eq!(gradient.color_at(0.5), RgbColor::new(128, 128, 128));
+		gradient.interpolation(Interpolation::LinearRgb);
+		assert_eq!(gradient.color_at(0.5), RgbColor::new(188, 188, 188));
+		assert_eq!(gradient.color_at(-1.0), black);
+		assert_eq!(gradient.color_at(2.0), white);
+
+		let rainbow = Gradient::rainbow();
+		assert_eq!(rainbow.color_at(0.1), RgbColor::new(255, 128, 0));
+		let cycle = Gradient::hue_cycle(120.0);
+		assert_eq!(cycle.colors(3)[0], cycle.colors(3)[2]);
+		assert_eq!(Gradient::new([white]).colors(2), [white, white]);
+	}
+
+	#[test]
+	fn falls_back() {
+		let red = RgbColor::new(255, 0, 0);
+		let gradient = Gradient::new([red, RgbColor::new(250, 0, 0)]);
+		assert_eq!(
+			gradient.apply_at(ColorLevel::Ansi256, "abc d"),
+			"\x1b[38;5;196mabc d\x1b[39m"
+		);
+		assert_eq!(gradient.apply_at(ColorLevel::None, "abc"), "abc");
+	}
+
+	#[test]
+	fn multiple_lines() {
+		let black = RgbColor::new(0, 0, 0);
+		let white = RgbColor::new(255, 255, 255);
+		let mut gradient = Gradient::new([black, white]);
+		gradient.interpolation(Interpolation::Srgb).background();
+
+		let lines = gradient.apply_lines_at(ColorLevel::TrueColor, "ab\nab");
+		let line = "\x1b[48;2;0;0;0ma\x1b[49m\x1b[48;2;255;255;255mb\x1b[49m";
+		assert_eq!(lines, format!("{}\n{}", line, line));
+
+		let diagonal =
+			gradient.apply_diagonal_at(ColorLevel::TrueColor, "a\nb");
+		assert_eq!(
+			diagonal,
+			"\x1b[48;2;0;0;0ma\x1b[49m\n\x1b[48;2;255;255;255mb\x1b[49m"
+		);
+	}
+}
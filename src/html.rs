@@ -0,0 +1,675 @@
+use crate::{parse_ansi, Chalk, ChalkType, Painted, Palette, Segment};
+
+use std::borrow::{Borrow, Cow};
+use std::fmt::{Display, Write};
+
+/// The name of the animation used for blinking text
+const BLINK_ANIMATION: &str = "chalk-blink";
+
+/// Turns text containing escape sequences into HTML, with the styles written
+/// inline. This is the same as [`HtmlRenderer::render_ansi`] with the
+/// default renderer.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::ansi_to_html;
+///
+/// assert_eq!(
+/// 	ansi_to_html("\x1b[31merror\x1b[39m: \x1b[1Aoops"),
+/// 	"<span style=\"color:#800000\">error</span>: oops"
+/// );
+/// ```
+pub fn ansi_to_html(text: &str) -> String {
+	HtmlRenderer::default().render_ansi(text)
+}
+
+/// Escapes the characters which have a special meaning in HTML.
+///
+/// If there aren't any, the text is returned without being copied.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::escape_html;
+///
+/// assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+/// ```
+pub fn escape_html(text: &str) -> Cow<'_, str> {
+	let special = |c| matches!(c, '&' | '<' | '>' | '"' | '\'');
+	if !text.contains(special) {
+		return Cow::Borrowed(text);
+	}
+
+	let mut escaped = String::with_capacity(text.len() + 16);
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			c => escaped.push(c),
+		}
+	}
+
+	Cow::Owned(escaped)
+}
+
+/// How the styles of HTML spans are written
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum HtmlStyle {
+	/// Each span has a `style` attribute with the CSS for its style
+	#[default]
+	Inline,
+	/// Each span has classes, which are defined by
+	/// [`HtmlRenderer::stylesheet`]. Colors which aren't in the 256 color
+	/// palette are still written inline, as custom properties which the
+	/// classes read.
+	Classes,
+}
+
+/// Turns styled text into HTML `<span>` elements.
+///
+/// Basic and ANSI colors are given their values from a [`Palette`], which is
+/// xterm's by default. Inverted text uses the
+/// `Canvas` and `CanvasText` system colors for any color which isn't set, and
+/// blinking text needs the animation from [`HtmlRenderer::stylesheet`], even
+/// when styles are written inline.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{Chalk, HtmlRenderer};
+///
+/// let mut chalk = Chalk::new();
+/// chalk.red().bold();
+///
+/// let mut html = HtmlRenderer::new();
+/// assert_eq!(
+/// 	html.render([(&chalk, "1 < 2"), (&Chalk::new(), "!")]),
+/// 	"<span style=\"color:#800000;font-weight:bold\">1 &lt; 2</span>!"
+/// );
+/// assert_eq!(
+/// 	html.classes().render_span(&chalk, "a"),
+/// 	"<span class=\"chalk-fg-1 chalk-bold\">a</span>"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct HtmlRenderer {
+	style: HtmlStyle,
+	prefix: String,
+	palette: Palette,
+}
+
+impl Default for HtmlRenderer {
+	fn default() -> Self {
+		HtmlRenderer {
+			style: HtmlStyle::default(),
+			prefix: "chalk-".to_string(),
+			palette: Palette::default(),
+		}
+	}
+}
+
+/// Checks whether every color and attribute which is set in the outer chalk
+/// is set the same way in the inner one
+fn contains(inner: &Chalk, outer: &Chalk) -> bool {
+	let color = |outer: &ChalkType, inner: &ChalkType| {
+		*outer == ChalkType::Default || outer == inner
+	};
+
+	color(&outer.foreground, &inner.foreground)
+		&& color(&outer.background, &inner.background)
+		&& color(&outer.underline_color, &inner.underline_color)
+		&& inner.style.contains(&outer.style)
+}
+
+/// Gets the part of the inner chalk which isn't already set by the outer one.
+/// Inverted text keeps both of its colors, since they're swapped.
+fn without(inner: &Chalk, outer: &Chalk) -> Chalk {
+	let mut difference = *inner;
+	difference.style = inner.style.without(&outer.style);
+	if inner.underline_color == outer.underline_color {
+		difference.underline_color = ChalkType::Default;
+	}
+	if inner.is_inverted() {
+		difference.invert();
+	} else {
+		if inner.foreground == outer.foreground {
+			difference.foreground = ChalkType::Default;
+		}
+		if inner.background == outer.background {
+			difference.background = ChalkType::Default;
+		}
+	}
+
+	difference
+}
+
+/// The values of the CSS `text-decoration` property for each line drawn
+/// through a chalk's text
+fn text_decorations(chalk: &Chalk) -> Vec<&'static str> {
+	let underline = [
+		(chalk.is_single_underlined(), "underline"),
+		(chalk.is_double_underlined(), "underline double"),
+		(chalk.is_curly_underlined(), "underline wavy"),
+		(chalk.is_dotted_underlined(), "underline dotted"),
+		(chalk.is_dashed_underlined(), "underline dashed"),
+	];
+	let underline = underline.iter().find(|(set, _)| *set).map(|(_, u)| *u);
+	underline
+		.into_iter()
+		.chain(chalk.is_struck_through().then_some("line-through"))
+		.chain(chalk.is_overlined().then_some("overline"))
+		.collect()
+}
+
+impl HtmlRenderer {
+	/// Creates a renderer which writes styles inline
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Writes styles in a `style` attribute. This is the default.
+	#[inline(always)]
+	pub fn inline(&mut self) -> &mut Self {
+		self.style = HtmlStyle::Inline;
+		self
+	}
+
+	/// Writes styles as classes from [`HtmlRenderer::stylesheet`]
+	#[inline(always)]
+	pub fn classes(&mut self) -> &mut Self {
+		self.style = HtmlStyle::Classes;
+		self
+	}
+
+	/// Sets what the names of the classes start with. This is `chalk-` by
+	/// default. Characters which can't be in a CSS class name are removed,
+	/// along with any digits it would start with.
+	pub fn class_prefix(&mut self, prefix: &str) -> &mut Self {
+		let mut prefix: String = prefix
+			.chars()
+			.filter(|&c| c.is_alphanumeric() || c == '-' || c == '_')
+			.collect();
+		// a name can't start with a digit, even after a single hyphen
+		let start =
+			(prefix.starts_with('-') && !prefix.starts_with("--")) as usize;
+		let digits = prefix[start..]
+			.bytes()
+			.take_while(u8::is_ascii_digit)
+			.count();
+		prefix.replace_range(start..start + digits, "");
+		self.prefix = prefix;
+		self
+	}
+
+	/// Sets the colors used for basic and ANSI colors
+	pub fn palette(&mut self, palette: Palette) -> &mut Self {
+		self.palette = palette;
+		self
+	}
+
+	/// Gets the CSS for a color
+	fn css_color(&self, color: &ChalkType) -> Option<String> {
+		self.palette.resolve(color).map(|color| color.to_hex())
+	}
+
+	/// The CSS declarations for the colors and attributes of a chalk
+	fn declarations(&self, chalk: &Chalk) -> Vec<String> {
+		let mut declarations = Vec::new();
+		let foreground = self.css_color(&chalk.foreground);
+		let background = self.css_color(&chalk.background);
+		let (foreground, background) = if chalk.is_inverted() {
+			(
+				Some(background.unwrap_or_else(|| "Canvas".to_string())),
+				Some(foreground.unwrap_or_else(|| "CanvasText".to_string())),
+			)
+		} else {
+			(foreground, background)
+		};
+
+		if let Some(color) = foreground {
+			declarations.push(format!("color:{}", color));
+		}
+		if let Some(color) = background {
+			declarations.push(format!("background-color:{}", color));
+		}
+		if chalk.is_bold() {
+			declarations.push("font-weight:bold".to_string());
+		}
+		if chalk.is_dim() {
+			declarations.push("opacity:0.5".to_string());
+		}
+		if chalk.is_italicized() {
+			declarations.push("font-style:italic".to_string());
+		}
+		let decorations = text_decorations(chalk);
+		if !decorations.is_empty() {
+			let decoration = decorations.join(" ");
+			declarations.push(format!("text-decoration:{}", decoration));
+		}
+		if let Some(color) = self.css_color(&chalk.underline_color) {
+			declarations.push(format!("text-decoration-color:{}", color));
+		}
+		if chalk.is_framed() {
+			declarations.push("outline:1px solid".to_string());
+		}
+		if chalk.is_encircled() {
+			declarations.push("border:1px solid;border-radius:1em".to_string());
+		}
+		if chalk.is_superscript() {
+			declarations.push("vertical-align:super;font-size:smaller".into());
+		}
+		if chalk.is_subscript() {
+			declarations.push("vertical-align:sub;font-size:smaller".into());
+		}
+		if chalk.is_slow_blinking() {
+			declarations.push(format!(
+				"animation:{} 1s step-end infinite",
+				BLINK_ANIMATION
+			));
+		}
+		if chalk.is_rapid_blinking() {
+			declarations.push(format!(
+				"animation:{} 0.25s step-end infinite",
+				BLINK_ANIMATION
+			));
+		}
+		if chalk.is_hidden() {
+			declarations.push("visibility:hidden".to_string());
+		}
+
+		declarations
+	}
+
+	/// The classes for a chalk, and any declarations which don't have one
+	fn class_list(&self, chalk: &Chalk) -> (Vec<String>, Vec<String>) {
+		let mut classes = Vec::new();
+		let mut declarations = Vec::new();
+		let colors = [("fg", &chalk.foreground), ("bg", &chalk.background)];
+		for (name, color) in colors.iter() {
+			match color.index() {
+				Some(i) => {
+					classes.push(format!("{}{}-{}", self.prefix, name, i))
+				}
+				None => {
+					// the class reads the color, so that inverting swaps it
+					if let Some(color) = self.css_color(color) {
+						classes.push(format!("{}{}", self.prefix, name));
+						declarations.push(format!(
+							"--{}{}:{}",
+							self.prefix, name, color
+						));
+					}
+				}
+			}
+		}
+
+		if let Some(color) = self.css_color(&chalk.underline_color) {
+			declarations.push(format!("text-decoration-color:{}", color));
+		}
+
+		let attributes = [
+			("bold", chalk.is_bold()),
+			("dim", chalk.is_dim()),
+			("italic", chalk.is_italicized()),
+			("underline", chalk.is_single_underlined()),
+			("double-underline", chalk.is_double_underlined()),
+			("curly-underline", chalk.is_curly_underlined()),
+			("dotted-underline", chalk.is_dotted_underlined()),
+			("dashed-underline", chalk.is_dashed_underlined()),
+			("strikethrough", chalk.is_struck_through()),
+			("overline", chalk.is_overlined()),
+			("framed", chalk.is_framed()),
+			("encircled", chalk.is_encircled()),
+			("superscript", chalk.is_superscript()),
+			("subscript", chalk.is_subscript()),
+			("blink", chalk.is_slow_blinking()),
+			("rapid-blink", chalk.is_rapid_blinking()),
+			("invert", chalk.is_inverted()),
+			("hidden", chalk.is_hidden()),
+		];
+		for (name, _) in attributes.iter().filter(|(_, set)| *set) {
+			classes.push(format!("{}{}", self.prefix, name));
+		}
+
+		// each line has its own class, but they all set the same property
+		let decorations = text_decorations(chalk);
+		if decorations.len() > 1 {
+			let decoration = decorations.join(" ");
+			declarations.push(format!("text-decoration:{}", decoration));
+		}
+
+		(classes, declarations)
+	}
+
+	/// Writes the opening tag of a span for a chalk, returning `false` if the
+	/// chalk doesn't have a style and no tag was written
+	fn write_open<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+		chalk: &Chalk,
+	) -> Result<bool, std::fmt::Error> {
+		let (classes, declarations) = match self.style {
+			HtmlStyle::Inline => (Vec::new(), self.declarations(chalk)),
+			HtmlStyle::Classes => self.class_list(chalk),
+		};
+
+		if classes.is_empty() && declarations.is_empty() {
+			return Ok(false);
+		}
+
+		w.write_str("<span")?;
+		if !classes.is_empty() {
+			write!(w, " class=\"{}\"", classes.join(" "))?;
+		}
+		if !declarations.is_empty() {
+			write!(w, " style=\"{}\"", declarations.join(";"))?;
+		}
+		w.write_str(">")?;
+		Ok(true)
+	}
+
+	/// Writes a piece of styled text as a span, inside a link if it has one
+	fn write_span<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+		chalk: &Chalk,
+		url: Option<&str>,
+		text: &str,
+	) -> std::fmt::Result {
+		if let Some(url) = url {
+			write!(w, "<a href=\"{}\">", escape_html(url))?;
+		}
+		let open = self.write_open(w, chalk)?;
+		w.write_str(&escape_html(text))?;
+		if open {
+			w.write_str("</span>")?;
+		}
+		if url.is_some() {
+			w.write_str("</a>")?;
+		}
+
+		Ok(())
+	}
+
+	/// Turns a piece of styled text into a span, which is put in a link if
+	/// the chalk is a hyperlink. Text without any style isn't put in a span.
+	pub fn render_span(&self, chalk: &Chalk, text: &str) -> String {
+		let mut html = String::new();
+		// writing to a string can't fail
+		let _ = self.write_span(&mut html, chalk, chalk.url(), text);
+		html
+	}
+
+	/// Turns a painted value into a span, which is put in a link if the
+	/// value is a hyperlink
+	pub fn render_painted<T: Display>(&self, painted: &Painted<T>) -> String {
+		let mut html = String::new();
+		let text = painted.get_ref().to_string();
+		let _ =
+			self.write_span(&mut html, painted.chalk(), painted.url(), &text);
+		html
+	}
+
+	/// Turns pieces of styled text into spans, such as the ones from
+	/// [`crate::Markup::spans`]
+	pub fn render<C: Borrow<Chalk>, S: AsRef<str>>(
+		&self,
+		spans: impl IntoIterator<Item = (C, S)>,
+	) -> String {
+		let mut html = String::new();
+		for (chalk, text) in spans {
+			let _ =
+				self.write_span(&mut html, chalk.borrow(), None, text.as_ref());
+		}
+
+		html
+	}
+
+	/// Turns text containing escape sequences into HTML, such as the output
+	/// of another program.
+	///
+	/// Resets and codes which turn off a single attribute are followed.
+	/// Spans are nested when the style of some text only adds to the style of
+	/// the text before it. Escape sequences which aren't SGR sequences, such
+	/// as cursor movement, are dropped.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::HtmlRenderer;
+	///
+	/// let mut html = HtmlRenderer::new();
+	/// html.classes();
+	/// assert_eq!(
+	/// 	html.render_ansi("\x1b[1mwarning: \x1b[33mx\x1b[22m\x1b[2K < y\x1b[0m"),
+	/// 	"<span class=\"chalk-bold\">warning: \
+	/// 	<span class=\"chalk-fg-3\">x</span></span>\
+	/// 	<span class=\"chalk-fg-3\"> &lt; y</span>"
+	/// );
+	/// ```
+	pub fn render_ansi(&self, text: &str) -> String {
+		let mut html = String::new();
+		// the full style inside each open span
+		let mut open: Vec<Chalk> = Vec::new();
+
+		for segment in parse_ansi(text) {
+			let Segment::Text(chalk, text) = segment else {
+				continue;
+			};
+
+			while let Some(outer) = open.last() {
+				if contains(&chalk, outer) {
+					break;
+				}
+				open.pop();
+				html.push_str("</span>");
+			}
+
+			let outer = open.last().cloned().unwrap_or_default();
+			if outer != chalk {
+				let difference = without(&chalk, &outer);
+				// writing to a string can't fail
+				if let Ok(true) = self.write_open(&mut html, &difference) {
+					open.push(chalk);
+				}
+			}
+			html.push_str(&escape_html(&text));
+		}
+
+		for _ in open {
+			html.push_str("</span>");
+		}
+
+		html
+	}
+
+	/// Generates the stylesheet which defines the classes for every color in
+	/// the 256 color palette and every attribute, along with the animation
+	/// for blinking text
+	pub fn stylesheet(&self) -> String {
+		let prefix = &self.prefix;
+		let mut css = String::new();
+		for i in 0..=255 {
+			let color = self.palette.get(i).to_hex();
+			let _ = writeln!(
+				css,
+				".{p}fg-{i} {{ --{p}fg: {c}; color: {c}; }}",
+				p = prefix,
+				i = i,
+				c = color
+			);
+		}
+		for i in 0..=255 {
+			let color = self.palette.get(i).to_hex();
+			let _ = writeln!(
+				css,
+				".{p}bg-{i} {{ --{p}bg: {c}; background-color: {c}; }}",
+				p = prefix,
+				i = i,
+				c = color
+			);
+		}
+		let _ = writeln!(
+			css,
+			".{p}fg {{ color: var(--{p}fg); }}\n\
+			.{p}bg {{ background-color: var(--{p}bg); }}",
+			p = prefix
+		);
+
+		let attributes = [
+			("bold", "font-weight: bold;".to_string()),
+			("dim", "opacity: 0.5;".to_string()),
+			("italic", "font-style: italic;".to_string()),
+			("underline", "text-decoration: underline;".to_string()),
+			(
+				"double-underline",
+				"text-decoration: underline double;".to_string(),
+			),
+			(
+				"curly-underline",
+				"text-decoration: underline wavy;".to_string(),
+			),
+			(
+				"dotted-underline",
+				"text-decoration: underline dotted;".to_string(),
+			),
+			(
+				"dashed-underline",
+				"text-decoration: underline dashed;".to_string(),
+			),
+			(
+				"strikethrough",
+				"text-decoration: line-through;".to_string(),
+			),
+			("overline", "text-decoration: overline;".to_string()),
+			("framed", "outline: 1px solid;".to_string()),
+			(
+				"encircled",
+				"border: 1px solid; border-radius: 1em;".to_string(),
+			),
+			(
+				"superscript",
+				"vertical-align: super; font-size: smaller;".to_string(),
+			),
+			(
+				"subscript",
+				"vertical-align: sub; font-size: smaller;".to_string(),
+			),
+			(
+				"blink",
+				format!("animation: {} 1s step-end infinite;", BLINK_ANIMATION),
+			),
+			(
+				"rapid-blink",
+				format!(
+					"animation: {} 0.25s step-end infinite;",
+					BLINK_ANIMATION
+				),
+			),
+			(
+				"invert",
+				format!(
+					"color: var(--{p}bg, Canvas); \
+					background-color: var(--{p}fg, CanvasText);",
+					p = prefix
+				),
+			),
+			("hidden", "visibility: hidden;".to_string()),
+		];
+		for (name, declarations) in attributes.iter() {
+			let _ = writeln!(css, ".{}{} {{ {} }}", prefix, name, declarations);
+		}
+
+		let _ = writeln!(
+			css,
+			"@keyframes {} {{ 50% {{ visibility: hidden; }} }}",
+			BLINK_ANIMATION
+		);
+		css
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn inline_styles() {
+		let html = HtmlRenderer::new();
+		let mut chalk = Chalk::new();
+		chalk.rgb(1, 2, 3).invert().double_underline();
+		assert_eq!(
+			html.render_span(&chalk, "'hi'"),
+			"<span style=\"color:Canvas;background-color:#010203;\
+			text-decoration:underline double\">&#39;hi&#39;</span>"
+		);
+		assert_eq!(html.render_span(&Chalk::new(), "a&b"), "a&amp;b");
+		assert_eq!(
+			html.render_painted(&Chalk::new().paint("c").link("/?a&b")),
+			"<a href=\"/?a&amp;b\">c</a>"
+		);
+
+		let mut chalk = Chalk::new();
+		chalk
+			.curly_underline()
+			.strikethrough()
+			.underline_rgb(255, 0, 0);
+		assert_eq!(
+			html.render_span(&chalk, "x"),
+			"<span style=\"text-decoration:underline wavy line-through;\
+			text-decoration-color:#ff0000\">x</span>"
+		);
+	}
+
+	#[test]
+	fn classes() {
+		let mut html = HtmlRenderer::new();
+		html.classes().class_prefix("c-");
+		let mut chalk = Chalk::new();
+		chalk.bg_ansi(200).rgb(255, 0, 0).blink().hide();
+		assert_eq!(
+			html.render_span(&chalk, "x"),
+			"<span class=\"c-fg c-bg-200 c-blink c-hidden\" \
+			style=\"--c-fg:#ff0000\">x</span>"
+		);
+
+		let mut chalk = Chalk::new();
+		chalk.rgb(255, 0, 0).bg_rgb(0, 0, 255).invert();
+		assert_eq!(
+			html.render_span(&chalk, "x"),
+			"<span class=\"c-fg c-bg c-invert\" \
+			style=\"--c-fg:#ff0000;--c-bg:#0000ff\">x</span>"
+		);
+
+		let css = html.stylesheet();
+		assert!(css.contains(".c-fg-9 { --c-fg: #ff0000; color: #ff0000; }"));
+		assert!(css.contains(".c-fg { color: var(--c-fg); }"));
+		let invert = css.find(".c-invert { color: var(--c-bg, Canvas);");
+		assert!(invert > css.find(".c-bg {"));
+		assert!(css.contains(".c-bg-255 { --c-bg: #eeeeee;"));
+		assert!(css.contains(".c-double-underline {"));
+		assert!(css.contains("@keyframes chalk-blink"));
+
+		assert_eq!(html.class_prefix("1a b<\"-").prefix, "ab-");
+		assert_eq!(html.class_prefix("-2-x").prefix, "--x");
+		assert_eq!(html.class_prefix("--3é").prefix, "--3é");
+	}
+
+	#[test]
+	fn nested_ansi() {
+		let html = HtmlRenderer::new();
+		assert_eq!(
+			html.render_ansi("\x1b[4ma\x1b[34mb\x1b[7mc\x1b[mz\x1b[1m"),
+			"<span style=\"text-decoration:underline\">a\
+			<span style=\"color:#000080\">b\
+			<span style=\"color:Canvas;background-color:#000080\">c\
+			</span></span></span>z"
+		);
+		assert_eq!(html.render_ansi("a\x1b]0;title\x07b"), "ab");
+	}
+}
@@ -0,0 +1,188 @@
+use std::env;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+/// The target of an OSC 8 hyperlink. Links with the same id are highlighted
+/// together by the terminal, even if they're split across lines.
+///
+/// [`crate::Painted`] owns its link, while a [`crate::Chalk`] borrows static
+/// strings so that it can stay `Copy`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Hyperlink<S = String> {
+	url: S,
+	id: Option<S>,
+}
+
+/// Writes bytes to a string, percent-encoding the ones which aren't kept
+fn percent_encode(out: &mut String, bytes: &[u8], keep: impl Fn(u8) -> bool) {
+	for &byte in bytes {
+		if keep(byte) {
+			out.push(byte as char);
+		} else {
+			// writing to a string can't fail
+			let _ = write!(out, "%{:02X}", byte);
+		}
+	}
+}
+
+impl Hyperlink {
+	/// Creates a link to a URL
+	pub(crate) fn new(url: &str, id: Option<&str>) -> Self {
+		Hyperlink {
+			url: url.to_string(),
+			id: id.map(str::to_string),
+		}
+	}
+
+	/// Borrows the link, so that it can be written like a chalk's link
+	#[inline(always)]
+	pub(crate) fn borrow(&self) -> Hyperlink<&str> {
+		Hyperlink {
+			url: &self.url,
+			id: self.id.as_deref(),
+		}
+	}
+
+	/// Writes the sequence which ends any link
+	#[inline(always)]
+	pub(crate) fn write_close<W: Write + ?Sized>(
+		w: &mut W,
+	) -> std::fmt::Result {
+		w.write_str("\x1b]8;;\x1b\\")
+	}
+}
+
+impl<'a> Hyperlink<&'a str> {
+	/// Creates a link to a URL, without copying it
+	#[inline(always)]
+	pub(crate) const fn borrowed(url: &'a str, id: Option<&'a str>) -> Self {
+		Hyperlink { url, id }
+	}
+}
+
+impl<S: AsRef<str>> Hyperlink<S> {
+	/// The URL the link points to
+	#[inline(always)]
+	pub(crate) fn url(&self) -> &str {
+		self.url.as_ref()
+	}
+
+	/// Writes the sequence which starts the link. Control characters would
+	/// end the sequence, so they're percent-encoded in the URL. The
+	/// characters which would end the parameters early are left out of the
+	/// id.
+	pub(crate) fn write_open<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+	) -> std::fmt::Result {
+		w.write_str("\x1b]8;")?;
+		if let Some(id) = &self.id {
+			w.write_str("id=")?;
+			for c in id.as_ref().chars() {
+				if !c.is_control() && c != ':' && c != ';' {
+					w.write_char(c)?;
+				}
+			}
+		}
+		w.write_char(';')?;
+		for c in self.url().chars() {
+			if c.is_control() {
+				let mut encoded = String::new();
+				let mut buffer = [0; 4];
+				let bytes = c.encode_utf8(&mut buffer).as_bytes();
+				percent_encode(&mut encoded, bytes, |_| false);
+				w.write_str(&encoded)?;
+			} else {
+				w.write_char(c)?;
+			}
+		}
+		w.write_str("\x1b\\")
+	}
+}
+
+/// Gets the name of this computer, for the host of a `file://` URL
+fn hostname() -> Option<String> {
+	let name = if cfg!(windows) {
+		env::var("COMPUTERNAME").ok()
+	} else {
+		std::fs::read_to_string("/proc/sys/kernel/hostname")
+			.or_else(|_| std::fs::read_to_string("/etc/hostname"))
+			.ok()
+			.or_else(|| env::var("HOSTNAME").ok())
+	};
+
+	name.map(|name| name.trim().to_string())
+		.filter(|name| !name.is_empty())
+}
+
+/// Makes a `file://` URL for a local path, which can be given to
+/// [`crate::Painted::link`]. Chalks can only link to static strings, so the
+/// URL can't be given to [`crate::Chalk::link`].
+///
+/// Relative paths are made absolute using the current directory. The name of
+/// this computer is used as the host, so that terminals can tell that the
+/// file is on the same machine. Any byte in the path other than a letter,
+/// digit, `-`, `.`, `_`, `~`, `/` or `:` is percent-encoded.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::file_url;
+///
+/// let url = file_url("/tmp/my notes.txt").unwrap();
+/// assert!(url.starts_with("file://"));
+/// assert!(url.ends_with("/tmp/my%20notes.txt"));
+/// ```
+pub fn file_url(path: impl AsRef<Path>) -> io::Result<String> {
+	let path = std::path::absolute(path)?;
+	let mut url = String::from("file://");
+	if let Some(host) = hostname() {
+		percent_encode(&mut url, host.as_bytes(), |b| {
+			b.is_ascii_alphanumeric() || b"-._".contains(&b)
+		});
+	}
+
+	#[cfg(unix)]
+	let path = {
+		use std::os::unix::ffi::OsStrExt;
+		path.as_os_str().as_bytes().to_vec()
+	};
+	#[cfg(not(unix))]
+	let path = {
+		let path = path.to_string_lossy().replace('\\', "/");
+		// drive letters need a slash in front of them
+		if path.starts_with('/') {
+			path.into_bytes()
+		} else {
+			format!("/{}", path).into_bytes()
+		}
+	};
+
+	percent_encode(&mut url, &path, |b| {
+		b.is_ascii_alphanumeric() || b"-._~/:".contains(&b)
+	});
+	Ok(url)
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn escapes() {
+		let link = Hyperlink::new("https://a.b/\x07c", Some("x;1:2"));
+		let mut sequence = String::new();
+		link.write_open(&mut sequence).unwrap();
+		assert_eq!(sequence, "\x1b]8;id=x12;https://a.b/%07c\x1b\\");
+		assert_eq!(
+			link.borrow(),
+			Hyperlink::borrowed("https://a.b/\x07c", Some("x;1:2"))
+		);
+
+		let mut encoded = String::new();
+		percent_encode(&mut encoded, "é %".as_bytes(), |b| b == b' ');
+		assert_eq!(encoded, "%C3%A9 %25");
+	}
+}
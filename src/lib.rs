@@ -29,37 +29,88 @@ use chalk_rs::Chalk;
 let mut chalk = Chalk::new();
 chalk.bold().println(&"Bold!");
 ```
+
+Escape sequences are only written when the terminal supports them. Output
+that is piped into a file, or that goes to a terminal with `TERM=dumb` or
+`NO_COLOR` set, is left unstyled. See [`ColorLevel`] for how this is detected.
 */
 
 #![allow(clippy::tabs_in_doc_comments)]
 
 mod ansi_chalk;
+mod appearance;
 mod basic_chalk;
+mod color_names;
+mod color_space;
+mod contrast;
+mod gradient;
+mod html;
+mod hyperlink;
+mod markup;
+mod names;
+mod painted;
+mod palette;
+mod parser;
+mod query;
 mod rgb_chalk;
+mod sgr;
+mod spec;
 mod style;
+mod support;
+mod svg;
+mod theme;
+mod tool_colors;
 mod utils;
+mod vision;
+mod width;
 
-use ansi_chalk::AnsiColor;
-use basic_chalk::BasicColor;
-use rgb_chalk::RgbColor;
+use hyperlink::Hyperlink;
+use sgr::Sgr;
 use style::StyleMap;
 
+pub use ansi_chalk::AnsiColor;
+pub use appearance::{AdaptiveColor, Appearance};
+pub use basic_chalk::BasicColor;
+pub use color_names::{ColorParseError, ColorParseErrorKind};
+pub use color_space::{Hsl, Hsv, Hwb, Lab, Oklab, Oklch};
+pub use gradient::{Gradient, Interpolation};
+pub use html::{ansi_to_html, escape_html, HtmlRenderer, HtmlStyle};
+pub use hyperlink::file_url;
+pub use markup::{Markup, MarkupError, MarkupErrorKind};
+pub use painted::Painted;
+pub use palette::Palette;
+pub use parser::{parse_ansi, Segment, SgrError, SgrParser};
+pub use query::Terminal;
+pub use rgb_chalk::RgbColor;
+pub use spec::{ChalkParseError, ChalkParseErrorKind};
+pub use support::{ColorLevel, Stream};
+pub use svg::SvgRenderer;
+pub use theme::{Theme, ThemeError, ThemeErrorKind};
+pub use tool_colors::{
+	gcc_colors, grep_colors, parse_color_list, parse_git_color, LsColors,
+};
+pub use vision::Deficiency;
+pub use width::{char_width, strip_ansi, visible_width};
+
 use std::fmt::Display;
+use std::fmt::Write;
+use std::io;
 use std::string::ToString;
 
-#[cfg(windows)]
-use winapi::{
-	shared::minwindef::DWORD, um::consoleapi::GetConsoleMode,
-	um::consoleapi::SetConsoleMode, um::processenv::GetStdHandle,
-	um::winbase::STD_OUTPUT_HANDLE,
-	um::wincon::ENABLE_VIRTUAL_TERMINAL_PROCESSING,
-};
-
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum ChalkType {
+/// A color which can be used for the foreground, background, or underline of
+/// a [`Chalk`].
+///
+/// Each type of color can be turned into one, so methods such as
+/// [`Chalk::fg`] accept any of them.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ChalkType {
+	/// The terminal's own color
 	Default,
+	/// One of the 16 basic colors
 	Basic(BasicColor),
+	/// A color in the 256 color palette
 	Ansi(AnsiColor),
+	/// A 24-bit color
 	Rgb(RgbColor),
 }
 
@@ -117,6 +168,26 @@ impl ChalkType {
 	pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
 		Self::Rgb(RgbColor::new(r, g, b))
 	}
+
+	/// Converts the color to the closest one that can be shown at the level
+	pub fn at_level(&self, level: ColorLevel) -> Self {
+		match (self, level) {
+			(Self::Rgb(c), ColorLevel::Ansi256) => Self::Ansi(c.to_ansi()),
+			(Self::Rgb(c), ColorLevel::Basic) => Self::Basic(c.to_basic()),
+			(Self::Ansi(c), ColorLevel::Basic) => Self::Basic(c.to_basic()),
+			(_, ColorLevel::None) => Self::Default,
+			_ => *self,
+		}
+	}
+
+	/// The index of the color in the 256 color palette, if it's in it
+	pub(crate) const fn index(&self) -> Option<u8> {
+		match self {
+			Self::Basic(color) => Some(color.index()),
+			Self::Ansi(color) => Some(color.as_num()),
+			_ => None,
+		}
+	}
 }
 
 impl Default for ChalkType {
@@ -126,52 +197,239 @@ impl Default for ChalkType {
 	}
 }
 
-#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+impl From<BasicColor> for ChalkType {
+	#[inline(always)]
+	fn from(color: BasicColor) -> Self {
+		Self::Basic(color)
+	}
+}
+
+impl From<AnsiColor> for ChalkType {
+	#[inline(always)]
+	fn from(color: AnsiColor) -> Self {
+		Self::Ansi(color)
+	}
+}
+
+impl From<RgbColor> for ChalkType {
+	#[inline(always)]
+	fn from(color: RgbColor) -> Self {
+		Self::Rgb(color)
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Chalk {
 	foreground: ChalkType,
 	background: ChalkType,
+	underline_color: ChalkType,
 	style: StyleMap,
+	link: Option<Hyperlink<&'static str>>,
 }
 
 impl Chalk {
 	#[inline(always)]
-	fn foreground_to_string(&self) -> String {
-		match &self.foreground {
-			ChalkType::Default => String::new(),
-			ChalkType::Basic(c) => format!("\x1b[{}m", c.as_foreground_color()),
-			ChalkType::Ansi(c) => format!("\x1b[38;5;{}m", c.as_num()),
-			ChalkType::Rgb(c) => format!(
-				"\x1b[38;2;{};{};{}m",
-				c.get_red(),
-				c.get_green(),
-				c.get_blue()
-			),
+	fn write_foreground(&self, sgr: &mut Sgr, level: ColorLevel) {
+		match self.foreground.at_level(level) {
+			ChalkType::Default => (),
+			ChalkType::Basic(c) => sgr.number(c.as_foreground_color()),
+			ChalkType::Ansi(c) => sgr.ansi("38", c.as_num()),
+			ChalkType::Rgb(c) => sgr.rgb("38", c),
 		}
 	}
 
 	#[inline(always)]
-	fn background_to_string(&self) -> String {
-		match &self.background {
-			ChalkType::Default => String::new(),
-			ChalkType::Basic(c) => format!("\x1b[{}m", c.as_background_color()),
-			ChalkType::Ansi(c) => format!("\x1b[48;5;{}m", c.as_num()),
-			ChalkType::Rgb(c) => format!(
-				"\x1b[48;2;{};{};{}m",
-				c.get_red(),
-				c.get_green(),
-				c.get_blue()
-			),
+	fn write_background(&self, sgr: &mut Sgr, level: ColorLevel) {
+		match self.background.at_level(level) {
+			ChalkType::Default => (),
+			ChalkType::Basic(c) => sgr.number(c.as_background_color()),
+			ChalkType::Ansi(c) => sgr.ansi("48", c.as_num()),
+			ChalkType::Rgb(c) => sgr.rgb("48", c),
 		}
 	}
+
+	/// Writes the underline color, which basic terminals can't show
+	#[inline(always)]
+	fn write_underline_color(&self, sgr: &mut Sgr, level: ColorLevel) {
+		if level < ColorLevel::Ansi256 {
+			return;
+		}
+
+		match self.underline_color.at_level(level) {
+			ChalkType::Default => (),
+			ChalkType::Basic(c) => sgr.ansi("58", c.index()),
+			ChalkType::Ansi(c) => sgr.ansi("58", c.as_num()),
+			ChalkType::Rgb(c) => sgr.rgb("58", c),
+		}
+	}
+
+	/// Gets the escape sequence for the chalk at the given color level.
+	///
+	/// Every attribute and color is set by a single sequence, which is empty
+	/// if the chalk doesn't set anything.
+	pub(crate) fn prefix(&self, level: ColorLevel) -> Sgr {
+		if !level.has_color() {
+			return Sgr::new();
+		}
+		support::enable_virtual_terminal();
+		if let Some(daltonized) = self.for_global_deficiency(level) {
+			return daltonized.colors(level);
+		}
+
+		self.colors(level)
+	}
+
+	/// Gets the escape sequence for the colors and attributes of the chalk,
+	/// as they are
+	fn colors(&self, level: ColorLevel) -> Sgr {
+		let mut sgr = Sgr::new();
+		self.style.write_open(&mut sgr);
+		self.write_foreground(&mut sgr, level);
+		self.write_background(&mut sgr, level);
+		self.write_underline_color(&mut sgr, level);
+		sgr.finish()
+	}
+
+	/// Writes the escape sequence for the chalk at the given color level
+	#[inline(always)]
+	pub(crate) fn write_prefix<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+		level: ColorLevel,
+	) -> std::fmt::Result {
+		w.write_str(self.prefix(level).as_str())
+	}
+
+	/// Applies the colors and attributes which are set in another chalk on
+	/// top of this one
+	pub(crate) fn layer(&mut self, top: &Chalk) {
+		if top.foreground != ChalkType::Default {
+			self.foreground = top.foreground;
+		}
+		if top.background != ChalkType::Default {
+			self.background = top.background;
+		}
+		if top.underline_color != ChalkType::Default {
+			self.underline_color = top.underline_color;
+		}
+		if top.link.is_some() {
+			self.link = top.link;
+		}
+		self.style.layer(&top.style);
+	}
+
+	/// Writes the escape sequence which undoes [`Chalk::write_prefix`].
+	///
+	/// Each attribute is turned off with its own parameter, rather than
+	/// resetting everything, so that the style of any text surrounding this
+	/// one is kept.
+	pub(crate) fn write_suffix<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+		level: ColorLevel,
+	) -> std::fmt::Result {
+		if !level.has_color() {
+			return Ok(());
+		}
+
+		let mut sgr = Sgr::new();
+		self.style.write_close(&mut sgr);
+		if self.foreground.at_level(level) != ChalkType::Default {
+			sgr.param("39");
+		}
+		if self.background.at_level(level) != ChalkType::Default {
+			sgr.param("49");
+		}
+		if level >= ColorLevel::Ansi256
+			&& self.underline_color != ChalkType::Default
+		{
+			sgr.param("59");
+		}
+		w.write_str(sgr.finish().as_str())
+	}
+
+	/// Gets the escape sequence for the chalk at the given color level.
+	///
+	/// Colors which the level can't display are replaced with the closest
+	/// color that it can. Nothing is returned for [`ColorLevel::None`].
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.rgb(255, 0, 0);
+	/// assert_eq!(chalk.render_at(ColorLevel::TrueColor), "\x1b[38;2;255;0;0m");
+	/// assert_eq!(chalk.render_at(ColorLevel::Ansi256), "\x1b[38;5;196m");
+	/// assert_eq!(chalk.render_at(ColorLevel::Basic), "\x1b[91m");
+	/// assert_eq!(chalk.render_at(ColorLevel::None), "");
+	/// ```
+	pub fn render_at(&self, level: ColorLevel) -> String {
+		let mut string = String::new();
+		// writing to a string can't fail
+		let _ = self.write_prefix(&mut string, level);
+		string
+	}
+
+	/// Formats a string using the style of the chalk at the given color level
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// let text = chalk.ansi(208).string_at(ColorLevel::Basic, &"orange");
+	/// assert_eq!(text, "\x1b[93morange\x1b[39m");
+	/// ```
+	pub fn string_at(
+		&self,
+		level: ColorLevel,
+		string: &dyn ToString,
+	) -> String {
+		self.paint(string.to_string()).at_level(level).to_string()
+	}
+
+	/// Styles a value without formatting it yet.
+	///
+	/// The returned [`Painted`] value implements [`Display`], writing the
+	/// escape sequences and the value straight into the formatter. This makes
+	/// it usable inside `format!`, `write!` and logging macros without
+	/// creating any intermediate strings.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let mut chalk = Chalk::new();
+	/// let error = chalk.red().bold().paint("error");
+	/// println!("{}: something went wrong", error);
+	/// ```
+	pub fn paint<T: Display>(&self, value: T) -> Painted<T> {
+		Painted::new(*self, value)
+	}
+
+	/// Styles a value for a writer whose color level was given by the caller,
+	/// so no stream is checked for hyperlinks
+	fn paint_at<T: Display>(&self, level: ColorLevel, value: T) -> Painted<T> {
+		self.paint(value)
+			.at_level(level)
+			.hyperlinks(support::terminal_shows_hyperlinks())
+	}
 }
 
+/// Writes the escape sequences for the chalk at the color level of standard
+/// output. The alternate form, `{:#}`, writes the chalk's [`Chalk::spec`]
+/// instead.
 impl Display for Chalk {
 	fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let mut string = String::with_capacity(9);
-		string += self.foreground_to_string().as_str();
-		string += self.background_to_string().as_str();
-		string += self.style.to_string().as_str();
-		write!(fmt, "{}", string)
+		if fmt.alternate() {
+			self.write_spec(fmt)
+		} else {
+			self.write_prefix(fmt, ColorLevel::stdout())
+		}
 	}
 }
 
@@ -187,30 +445,31 @@ impl Chalk {
 	/// let mut chalk = Chalk::new();
 	/// // the chalk can be used here
 	/// ```
-	pub fn new() -> Self {
-		// makes it work on windows
-		#[cfg(windows)]
-		unsafe {
-			static mut IS_SETUP: bool = false;
-
-			if !IS_SETUP {
-				let handle = GetStdHandle(STD_OUTPUT_HANDLE);
-				let mut dw_mode: DWORD = 0;
-				dw_mode |= GetConsoleMode(handle, &mut dw_mode) as u32;
-				dw_mode |= ENABLE_VIRTUAL_TERMINAL_PROCESSING;
-				SetConsoleMode(handle, dw_mode);
-
-				IS_SETUP = true;
-			}
+	///
+	/// This is a `const fn`, so chalks can be built in constants and statics
+	/// using the methods which start with `with_`:
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// const ERROR: Chalk = Chalk::new().with_red().with_bold();
+	/// ERROR.println(&"something went wrong");
+	/// ```
+	pub const fn new() -> Self {
+		Chalk {
+			foreground: ChalkType::Default,
+			background: ChalkType::Default,
+			underline_color: ChalkType::Default,
+			style: StyleMap::new(),
+			link: None,
 		}
-
-		Self::default()
 	}
 
 	/// Formats a string using the style of the given [`Chalk`].
 	///
 	/// This will return the string after being formatted to the console. When
-	/// using string literals, please use a reference.
+	/// using string literals, please use a reference. If standard output
+	/// doesn't support color, the string is returned without any escapes.
 	///
 	/// For example:
 	///
@@ -222,7 +481,7 @@ impl Chalk {
 	/// # Arguments
 	///
 	/// * `string` - The item to print out. It must implement [`ToString`]
-	/// and should be a reference
+	///   and should be a reference
 	///
 	/// # Example
 	///
@@ -233,7 +492,7 @@ impl Chalk {
 	/// let text = chalk.yellow().string(&"this is yellow");
 	/// ```
 	pub fn string(&self, string: &dyn ToString) -> String {
-		format!("{}{}\x1b[m", self.to_string(), string.to_string())
+		self.string_at(ColorLevel::stdout(), string)
 	}
 
 	/// Prints a string using the style of the given chalk.
@@ -252,7 +511,7 @@ impl Chalk {
 	/// # Arguments
 	///
 	/// * `string` - The item to format and output. It must implement [`ToString`]
-	/// and should be a reference.
+	///   and should be a reference.
 	///
 	/// # Example
 	///
@@ -284,7 +543,7 @@ impl Chalk {
 	/// # Arguments
 	///
 	/// * `string` - The item to format and output. It must implement [`ToString`]
-	/// and should be a reference.
+	///   and should be a reference.
 	///
 	/// # Example
 	///
@@ -299,138 +558,457 @@ impl Chalk {
 		println!("{}", output);
 		output
 	}
+
+	/// Prints a value to standard error using the style of the chalk.
+	///
+	/// The color level of standard error is used, so the value is unstyled if
+	/// standard error is redirected to a file. Unlike [`Chalk::print`], an
+	/// error is returned instead of panicking if the value can't be written.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.red().eprint("error: ").unwrap();
+	/// ```
+	pub fn eprint(&self, value: impl Display) -> io::Result<()> {
+		use std::io::Write as _;
+		let painted = self.paint(value).to_stream(Stream::Stderr);
+		write!(io::stderr().lock(), "{}", painted)
+	}
+
+	/// Prints a value to standard error using the style of the chalk,
+	/// followed by a new line.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.red().eprintln("something went wrong").unwrap();
+	/// ```
+	pub fn eprintln(&self, value: impl Display) -> io::Result<()> {
+		use std::io::Write as _;
+		let painted = self.paint(value).to_stream(Stream::Stderr);
+		writeln!(io::stderr().lock(), "{}", painted)
+	}
+
+	/// Writes a value to an [`io::Write`] using the style of the chalk, at
+	/// the given color level.
+	///
+	/// The level should be the one of the stream the writer ends up in, such
+	/// as [`ColorLevel::stdout`] or [`ColorLevel::stderr`]. If it has color,
+	/// hyperlinks are written when the terminal is known to show them.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	/// use std::io::BufWriter;
+	///
+	/// let mut chalk = Chalk::new();
+	/// let mut out = BufWriter::new(std::io::stderr());
+	/// chalk.green().write_to(&mut out, ColorLevel::stderr(), "done").unwrap();
+	/// ```
+	pub fn write_to<W: io::Write + ?Sized>(
+		&self,
+		writer: &mut W,
+		level: ColorLevel,
+		value: impl Display,
+	) -> io::Result<()> {
+		write!(writer, "{}", self.paint_at(level, value))
+	}
+
+	/// Writes a value to an [`io::Write`] using the style of the chalk at the
+	/// given color level, followed by a new line.
+	pub fn writeln_to<W: io::Write + ?Sized>(
+		&self,
+		writer: &mut W,
+		level: ColorLevel,
+		value: impl Display,
+	) -> io::Result<()> {
+		writeln!(writer, "{}", self.paint_at(level, value))
+	}
+
+	/// Writes a value to a [`std::fmt::Write`] using the style of the chalk,
+	/// at the given color level.
+	///
+	/// The level should be the one of the stream the text ends up in. If it
+	/// has color, hyperlinks are written when the terminal is known to show
+	/// them.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// let mut report = String::new();
+	/// let level = ColorLevel::stderr();
+	/// chalk.bold().write_fmt_to(&mut report, level, "Summary").unwrap();
+	/// ```
+	pub fn write_fmt_to<W: Write + ?Sized>(
+		&self,
+		writer: &mut W,
+		level: ColorLevel,
+		value: impl Display,
+	) -> std::fmt::Result {
+		write!(writer, "{}", self.paint_at(level, value))
+	}
+
+	/// Writes a value to a [`std::fmt::Write`] using the style of the chalk
+	/// at the given color level, followed by a new line.
+	pub fn writeln_fmt_to<W: Write + ?Sized>(
+		&self,
+		writer: &mut W,
+		level: ColorLevel,
+		value: impl Display,
+	) -> std::fmt::Result {
+		writeln!(writer, "{}", self.paint_at(level, value))
+	}
 }
 
 macro_rules! color_fg {
-	($fn_name: ident) => {
+	($fn_name: ident, $with_name: ident) => {
 		#[inline(always)]
-		pub fn $fn_name(&mut self) -> &mut Self {
+		pub const fn $fn_name(&mut self) -> &mut Self {
 			self.foreground = ChalkType::$fn_name();
 			self
 		}
+
+		#[inline(always)]
+		pub const fn $with_name(mut self) -> Self {
+			self.$fn_name();
+			self
+		}
 	};
 }
 
 impl Chalk {
+	/// Sets the foreground to any kind of color
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{BasicColor, Chalk, ColorLevel, RgbColor};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.fg(BasicColor::Green).bg(RgbColor::new(0, 0, 0));
+	/// assert_eq!(
+	/// 	chalk.render_at(ColorLevel::TrueColor),
+	/// 	"\x1b[32;48;2;0;0;0m"
+	/// );
+	/// ```
+	#[inline(always)]
+	pub fn fg(&mut self, color: impl Into<ChalkType>) -> &mut Self {
+		self.foreground = color.into();
+		self
+	}
+
+	/// Sets the background to any kind of color
+	#[inline(always)]
+	pub fn bg(&mut self, color: impl Into<ChalkType>) -> &mut Self {
+		self.background = color.into();
+		self
+	}
+
+	/// Sets the color of the underline to any kind of color
 	#[inline(always)]
-	pub fn default_color(&mut self) -> &mut Self {
+	pub fn underline_color(
+		&mut self,
+		color: impl Into<ChalkType>,
+	) -> &mut Self {
+		self.underline_color = color.into();
+		self
+	}
+
+	/// Sets the foreground, taking and returning the chalk so that it can be
+	/// used in constants.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ChalkType, ColorLevel};
+	///
+	/// const LINK: Chalk =
+	/// 	Chalk::new().with_fg(ChalkType::blue()).with_underline();
+	/// assert_eq!(LINK.render_at(ColorLevel::Basic), "\x1b[4;34m");
+	/// ```
+	#[inline(always)]
+	pub const fn with_fg(mut self, color: ChalkType) -> Self {
+		self.foreground = color;
+		self
+	}
+
+	/// Sets the background, taking and returning the chalk
+	#[inline(always)]
+	pub const fn with_bg(mut self, color: ChalkType) -> Self {
+		self.background = color;
+		self
+	}
+
+	/// Sets the color of the underline, taking and returning the chalk
+	#[inline(always)]
+	pub const fn with_underline_color(mut self, color: ChalkType) -> Self {
+		self.underline_color = color;
+		self
+	}
+
+	/// The color of the text
+	#[inline(always)]
+	pub const fn foreground(&self) -> &ChalkType {
+		&self.foreground
+	}
+
+	/// The color behind the text
+	#[inline(always)]
+	pub const fn background(&self) -> &ChalkType {
+		&self.background
+	}
+
+	#[inline(always)]
+	pub const fn default_color(&mut self) -> &mut Self {
 		self.foreground = ChalkType::default();
 		self
 	}
 
-	color_fg!(black);
-	color_fg!(red);
-	color_fg!(green);
-	color_fg!(yellow);
-	color_fg!(blue);
-	color_fg!(magenta);
-	color_fg!(cyan);
-	color_fg!(light_gray);
-	color_fg!(light_grey);
-	color_fg!(gray);
-	color_fg!(grey);
-	color_fg!(light_black);
-	color_fg!(light_red);
-	color_fg!(light_green);
-	color_fg!(light_yellow);
-	color_fg!(light_blue);
-	color_fg!(light_magenta);
-	color_fg!(light_cyan);
-	color_fg!(white);
-
-	#[inline(always)]
-	pub fn ansi(&mut self, color: u8) -> &mut Self {
+	#[inline(always)]
+	pub const fn with_default_color(mut self) -> Self {
+		self.default_color();
+		self
+	}
+
+	color_fg!(black, with_black);
+	color_fg!(red, with_red);
+	color_fg!(green, with_green);
+	color_fg!(yellow, with_yellow);
+	color_fg!(blue, with_blue);
+	color_fg!(magenta, with_magenta);
+	color_fg!(cyan, with_cyan);
+	color_fg!(light_gray, with_light_gray);
+	color_fg!(light_grey, with_light_grey);
+	color_fg!(gray, with_gray);
+	color_fg!(grey, with_grey);
+	color_fg!(light_black, with_light_black);
+	color_fg!(light_red, with_light_red);
+	color_fg!(light_green, with_light_green);
+	color_fg!(light_yellow, with_light_yellow);
+	color_fg!(light_blue, with_light_blue);
+	color_fg!(light_magenta, with_light_magenta);
+	color_fg!(light_cyan, with_light_cyan);
+	color_fg!(white, with_white);
+
+	#[inline(always)]
+	pub const fn ansi(&mut self, color: u8) -> &mut Self {
 		self.foreground = ChalkType::ansi(color);
 		self
 	}
 
 	#[inline(always)]
-	pub fn rgb(&mut self, r: u8, g: u8, b: u8) -> &mut Self {
+	pub const fn with_ansi(mut self, color: u8) -> Self {
+		self.ansi(color);
+		self
+	}
+
+	#[inline(always)]
+	pub const fn rgb(&mut self, r: u8, g: u8, b: u8) -> &mut Self {
 		self.foreground = ChalkType::rgb(r, g, b);
 		self
 	}
+
+	#[inline(always)]
+	pub const fn with_rgb(mut self, r: u8, g: u8, b: u8) -> Self {
+		self.rgb(r, g, b);
+		self
+	}
+
+	/// Sets the foreground to a color given by its hue in degrees, and its
+	/// saturation and lightness from 0 to 1
+	pub fn hsl(&mut self, h: f32, s: f32, l: f32) -> &mut Self {
+		self.foreground = ChalkType::Rgb(Hsl::new(h, s, l).into());
+		self
+	}
+
+	/// Sets the foreground to a color given by its hue in degrees, and its
+	/// saturation and value from 0 to 1
+	pub fn hsv(&mut self, h: f32, s: f32, v: f32) -> &mut Self {
+		self.foreground = ChalkType::Rgb(Hsv::new(h, s, v).into());
+		self
+	}
+
+	/// Sets the foreground to a color given by its hue in degrees, and its
+	/// whiteness and blackness from 0 to 1
+	pub fn hwb(&mut self, h: f32, w: f32, b: f32) -> &mut Self {
+		self.foreground = ChalkType::Rgb(Hwb::new(h, w, b).into());
+		self
+	}
+
+	/// Sets the foreground to an OKLCH color. Colors which sRGB can't show
+	/// have their chroma reduced until they fit.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.oklch(0.7, 0.15, 30.0).println(&"salmon");
+	/// ```
+	pub fn oklch(&mut self, l: f32, c: f32, h: f32) -> &mut Self {
+		self.foreground = ChalkType::Rgb(Oklch::new(l, c, h).into());
+		self
+	}
 }
 
 macro_rules! color_bg {
-	($fn_name: ident, $color: ident) => {
+	($fn_name: ident, $with_name: ident, $color: ident) => {
 		#[inline(always)]
-		pub fn $fn_name(&mut self) -> &mut Self {
+		pub const fn $fn_name(&mut self) -> &mut Self {
 			self.background = ChalkType::$color();
 			self
 		}
+
+		#[inline(always)]
+		pub const fn $with_name(mut self) -> Self {
+			self.$fn_name();
+			self
+		}
 	};
 }
 
 impl Chalk {
-	color_bg!(default_background, default);
-	color_bg!(bg_black, black);
-	color_bg!(bg_red, red);
-	color_bg!(bg_green, green);
-	color_bg!(bg_yellow, yellow);
-	color_bg!(bg_blue, blue);
-	color_bg!(bg_magenta, magenta);
-	color_bg!(bg_cyan, cyan);
-	color_bg!(bg_light_gray, light_gray);
-	color_bg!(bg_light_grey, light_grey);
-	color_bg!(bg_gray, gray);
-	color_bg!(bg_grey, grey);
-	color_bg!(bg_light_black, light_black);
-	color_bg!(bg_light_red, light_red);
-	color_bg!(bg_light_green, light_green);
-	color_bg!(bg_light_yellow, light_yellow);
-	color_bg!(bg_light_blue, light_blue);
-	color_bg!(bg_light_magenta, light_magenta);
-	color_bg!(bg_light_cyan, light_cyan);
-	color_bg!(bg_white, white);
-
-	#[inline(always)]
-	pub fn bg_ansi(&mut self, color: u8) -> &mut Self {
+	color_bg!(default_background, with_default_background, default);
+	color_bg!(bg_black, with_bg_black, black);
+	color_bg!(bg_red, with_bg_red, red);
+	color_bg!(bg_green, with_bg_green, green);
+	color_bg!(bg_yellow, with_bg_yellow, yellow);
+	color_bg!(bg_blue, with_bg_blue, blue);
+	color_bg!(bg_magenta, with_bg_magenta, magenta);
+	color_bg!(bg_cyan, with_bg_cyan, cyan);
+	color_bg!(bg_light_gray, with_bg_light_gray, light_gray);
+	color_bg!(bg_light_grey, with_bg_light_grey, light_grey);
+	color_bg!(bg_gray, with_bg_gray, gray);
+	color_bg!(bg_grey, with_bg_grey, grey);
+	color_bg!(bg_light_black, with_bg_light_black, light_black);
+	color_bg!(bg_light_red, with_bg_light_red, light_red);
+	color_bg!(bg_light_green, with_bg_light_green, light_green);
+	color_bg!(bg_light_yellow, with_bg_light_yellow, light_yellow);
+	color_bg!(bg_light_blue, with_bg_light_blue, light_blue);
+	color_bg!(bg_light_magenta, with_bg_light_magenta, light_magenta);
+	color_bg!(bg_light_cyan, with_bg_light_cyan, light_cyan);
+	color_bg!(bg_white, with_bg_white, white);
+
+	#[inline(always)]
+	pub const fn bg_ansi(&mut self, color: u8) -> &mut Self {
 		self.background = ChalkType::ansi(color);
 		self
 	}
 
 	#[inline(always)]
-	pub fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> &mut Self {
+	pub const fn with_bg_ansi(mut self, color: u8) -> Self {
+		self.bg_ansi(color);
+		self
+	}
+
+	#[inline(always)]
+	pub const fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> &mut Self {
 		self.background = ChalkType::rgb(r, g, b);
 		self
 	}
+
+	#[inline(always)]
+	pub const fn with_bg_rgb(mut self, r: u8, g: u8, b: u8) -> Self {
+		self.bg_rgb(r, g, b);
+		self
+	}
+
+	/// Sets the background to a color given by its hue in degrees, and its
+	/// saturation and lightness from 0 to 1
+	pub fn bg_hsl(&mut self, h: f32, s: f32, l: f32) -> &mut Self {
+		self.background = ChalkType::Rgb(Hsl::new(h, s, l).into());
+		self
+	}
+
+	/// Sets the background to a color given by its hue in degrees, and its
+	/// saturation and value from 0 to 1
+	pub fn bg_hsv(&mut self, h: f32, s: f32, v: f32) -> &mut Self {
+		self.background = ChalkType::Rgb(Hsv::new(h, s, v).into());
+		self
+	}
+
+	/// Sets the background to a color given by its hue in degrees, and its
+	/// whiteness and blackness from 0 to 1
+	pub fn bg_hwb(&mut self, h: f32, w: f32, b: f32) -> &mut Self {
+		self.background = ChalkType::Rgb(Hwb::new(h, w, b).into());
+		self
+	}
+
+	/// Sets the background to an OKLCH color. Colors which sRGB can't show
+	/// have their chroma reduced until they fit.
+	pub fn bg_oklch(&mut self, l: f32, c: f32, h: f32) -> &mut Self {
+		self.background = ChalkType::Rgb(Oklch::new(l, c, h).into());
+		self
+	}
 }
 
 macro_rules! set_style {
-	($fn_name: ident) => {
+	($fn_name: ident, $with_name: ident) => {
 		#[inline(always)]
-		pub fn $fn_name(&mut self) -> &mut Self {
+		pub const fn $fn_name(&mut self) -> &mut Self {
 			self.style.$fn_name();
 			self
 		}
+
+		#[inline(always)]
+		pub const fn $with_name(mut self) -> Self {
+			self.$fn_name();
+			self
+		}
 	};
 }
 
 macro_rules! check_style {
 	($fn_name: ident) => {
 		#[inline(always)]
-		pub fn $fn_name(&self) -> bool {
+		pub const fn $fn_name(&self) -> bool {
 			self.style.$fn_name()
 		}
 	};
 }
 
 impl Chalk {
-	set_style!(reset_style);
-	set_style!(reset_weight);
-	set_style!(bold);
-	set_style!(dim);
-	set_style!(italic);
-	set_style!(unitalic);
-	set_style!(no_underline);
-	set_style!(underline);
-	set_style!(double_underline);
-	set_style!(stop_blink);
-	set_style!(blink);
-	set_style!(invert);
-	set_style!(uninvert);
-	set_style!(hide);
-	set_style!(unhide);
+	set_style!(reset_style, with_reset_style);
+	set_style!(reset_weight, with_reset_weight);
+	set_style!(bold, with_bold);
+	set_style!(dim, with_dim);
+	set_style!(italic, with_italic);
+	set_style!(unitalic, with_unitalic);
+	set_style!(no_underline, with_no_underline);
+	set_style!(underline, with_underline);
+	set_style!(double_underline, with_double_underline);
+	set_style!(curly_underline, with_curly_underline);
+	set_style!(dotted_underline, with_dotted_underline);
+	set_style!(dashed_underline, with_dashed_underline);
+	set_style!(stop_blink, with_stop_blink);
+	set_style!(blink, with_blink);
+	set_style!(rapid_blink, with_rapid_blink);
+	set_style!(invert, with_invert);
+	set_style!(uninvert, with_uninvert);
+	set_style!(hide, with_hide);
+	set_style!(unhide, with_unhide);
+	set_style!(strikethrough, with_strikethrough);
+	set_style!(no_strikethrough, with_no_strikethrough);
+	set_style!(overline, with_overline);
+	set_style!(no_overline, with_no_overline);
+	set_style!(frame, with_frame);
+	set_style!(encircle, with_encircle);
+	set_style!(no_frame, with_no_frame);
+	set_style!(superscript, with_superscript);
+	set_style!(subscript, with_subscript);
+	set_style!(no_script, with_no_script);
 
 	check_style!(is_normal_weight);
 	check_style!(is_bold);
@@ -439,13 +1017,144 @@ impl Chalk {
 	check_style!(has_underlines);
 	check_style!(is_single_underlined);
 	check_style!(is_double_underlined);
+	check_style!(is_curly_underlined);
+	check_style!(is_dotted_underlined);
+	check_style!(is_dashed_underlined);
 	check_style!(is_blinking);
+	check_style!(is_slow_blinking);
+	check_style!(is_rapid_blinking);
 	check_style!(is_inverted);
 	check_style!(is_hidden);
+	check_style!(is_struck_through);
+	check_style!(is_overlined);
+	check_style!(is_framed);
+	check_style!(is_encircled);
+	check_style!(is_superscript);
+	check_style!(is_subscript);
 
-	pub fn num_underlines(&self) -> u8 {
+	pub const fn num_underlines(&self) -> u8 {
 		self.style.num_underlines()
 	}
+
+	/// Resets the color of the underline to the color of the text
+	#[inline(always)]
+	pub const fn default_underline_color(&mut self) -> &mut Self {
+		self.underline_color = ChalkType::Default;
+		self
+	}
+
+	#[inline(always)]
+	pub const fn with_default_underline_color(mut self) -> Self {
+		self.default_underline_color();
+		self
+	}
+
+	/// Sets the color of the underline, separately from the color of the
+	/// text. This is only shown by terminals with at least 256 colors.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.curly_underline().underline_ansi(196);
+	/// assert_eq!(
+	/// 	chalk.string_at(ColorLevel::Ansi256, &"typo"),
+	/// 	"\x1b[4:3;58;5;196mtypo\x1b[24;59m"
+	/// );
+	/// ```
+	#[inline(always)]
+	pub const fn underline_ansi(&mut self, color: u8) -> &mut Self {
+		self.underline_color = ChalkType::ansi(color);
+		self
+	}
+
+	#[inline(always)]
+	pub const fn with_underline_ansi(mut self, color: u8) -> Self {
+		self.underline_ansi(color);
+		self
+	}
+
+	/// Sets the color of the underline to an RGB color
+	#[inline(always)]
+	pub const fn underline_rgb(&mut self, r: u8, g: u8, b: u8) -> &mut Self {
+		self.underline_color = ChalkType::rgb(r, g, b);
+		self
+	}
+
+	#[inline(always)]
+	pub const fn with_underline_rgb(mut self, r: u8, g: u8, b: u8) -> Self {
+		self.underline_rgb(r, g, b);
+		self
+	}
+}
+
+/** Hyperlinks */
+impl Chalk {
+	/// Makes the text a hyperlink to a URL, using OSC 8.
+	///
+	/// The link is only written by [`Chalk::paint`] and the methods which
+	/// print text, since the [`Display`] impl of a chalk can't end it. If
+	/// the terminal isn't known to show hyperlinks, or color is turned off,
+	/// the text is written without the link.
+	///
+	/// The URL has to be static so that chalks can stay `Copy`. Links which
+	/// are made at runtime, such as by [`file_url`], can be given to
+	/// [`Painted::link`] instead.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.underline().link("https://example.com");
+	/// let text = chalk.paint("docs").at_level(ColorLevel::Basic);
+	/// assert_eq!(
+	/// 	text.clone().hyperlinks(true).to_string(),
+	/// 	"\x1b[4m\x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\\x1b[24m"
+	/// );
+	/// assert_eq!(text.hyperlinks(false).to_string(), "\x1b[4mdocs\x1b[24m");
+	/// ```
+	#[inline(always)]
+	pub const fn link(&mut self, url: &'static str) -> &mut Self {
+		self.link = Some(Hyperlink::borrowed(url, None));
+		self
+	}
+
+	#[inline(always)]
+	pub const fn with_link(mut self, url: &'static str) -> Self {
+		self.link(url);
+		self
+	}
+
+	/// Makes the text a hyperlink with an id. Terminals highlight every
+	/// link with the same id and URL together, such as a link which is
+	/// split across several lines. The id can't contain `:` or `;`, so they
+	/// are left out when it's written.
+	#[inline(always)]
+	pub const fn link_with_id(
+		&mut self,
+		url: &'static str,
+		id: &'static str,
+	) -> &mut Self {
+		self.link = Some(Hyperlink::borrowed(url, Some(id)));
+		self
+	}
+
+	/// Removes the hyperlink
+	#[inline(always)]
+	pub const fn unlink(&mut self) -> &mut Self {
+		self.link = None;
+		self
+	}
+
+	/// The URL the text links to, if it's a hyperlink
+	#[inline(always)]
+	pub fn url(&self) -> Option<&str> {
+		self.link.as_ref().map(Hyperlink::url)
+	}
 }
 
 #[cfg(test)]
@@ -453,6 +1162,62 @@ mod test {
 
 	use crate::*;
 
+	#[test]
+	fn write_to_buffers() {
+		let mut chalk = Chalk::new();
+		chalk.red();
+
+		let mut bytes = Vec::new();
+		let writer: &mut dyn std::io::Write = &mut bytes;
+		chalk.writeln_to(writer, ColorLevel::Basic, 42).unwrap();
+		let expected = chalk.string_at(ColorLevel::Basic, &42) + "\n";
+		assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+
+		let mut string = String::new();
+		chalk
+			.writeln_fmt_to(&mut string, ColorLevel::Ansi256, "a")
+			.unwrap();
+		chalk
+			.write_fmt_to(&mut string, ColorLevel::None, "b")
+			.unwrap();
+		assert_eq!(string, chalk.string_at(ColorLevel::Ansi256, &"a") + "\nb");
+	}
+
+	#[test]
+	fn nested_strings() {
+		let level = ColorLevel::Basic;
+		let inner = Chalk::new().red().string_at(level, &"red");
+		let mut outer = Chalk::new();
+		outer.blue().bold();
+		let text = format!("a {} b", inner);
+		assert_eq!(
+			outer.string_at(level, &text),
+			"\x1b[1;34ma \x1b[31mred\x1b[39m\x1b[1;34m b\x1b[22;39m"
+		);
+
+		let reset = outer.string_at(level, &"a \x1b[mb");
+		assert_eq!(reset, "\x1b[1;34ma \x1b[m\x1b[1;34mb\x1b[22;39m");
+
+		let colors = outer.string_at(level, &"\x1b[38;5;39mc");
+		assert_eq!(colors, "\x1b[1;34m\x1b[38;5;39mc\x1b[22;39m");
+	}
+
+	#[test]
+	fn single_sequences() {
+		let level = ColorLevel::Basic;
+		let chalk = Chalk::new().with_bold().with_underline().with_red();
+		let chalk = chalk.with_bg_blue();
+		assert_eq!(chalk.render_at(level), "\x1b[1;4;31;44m");
+		assert_eq!(
+			chalk.string_at(level, &"text"),
+			"\x1b[1;4;31;44mtext\x1b[22;24;39;49m"
+		);
+
+		let plain = Chalk::new();
+		assert_eq!(plain.render_at(level), "");
+		assert_eq!(plain.string_at(level, &"a \x1b[mb"), "a \x1b[mb");
+	}
+
 	#[test]
 	fn is_setup() {
 		Chalk::new().red().println(&"This is red");
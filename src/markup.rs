@@ -0,0 +1,341 @@
+use crate::names::apply_name;
+use crate::{Chalk, ColorLevel};
+
+use std::error::Error;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+/// The reason a piece of markup couldn't be parsed
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum MarkupErrorKind {
+	/// A style name which isn't a chalk method
+	UnknownStyle(String),
+	/// A `{` which doesn't have any style names after it
+	MissingStyle,
+	/// A `{` which is never closed
+	Unclosed,
+	/// A `}` which doesn't close anything
+	UnexpectedClose,
+	/// A `\` at the end of the markup
+	TrailingBackslash,
+}
+
+/// An error from parsing [`Markup`]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MarkupError {
+	kind: MarkupErrorKind,
+	position: usize,
+}
+
+impl MarkupError {
+	/// What went wrong
+	pub fn kind(&self) -> &MarkupErrorKind {
+		&self.kind
+	}
+
+	/// The byte offset in the markup where the error was found
+	pub const fn position(&self) -> usize {
+		self.position
+	}
+}
+
+impl Display for MarkupError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.kind {
+			MarkupErrorKind::UnknownStyle(name) => {
+				write!(f, "unknown style `{}`", name)?
+			}
+			MarkupErrorKind::MissingStyle => {
+				f.write_str("missing style name")?
+			}
+			MarkupErrorKind::Unclosed => f.write_str("unclosed `{`")?,
+			MarkupErrorKind::UnexpectedClose => {
+				f.write_str("unexpected `}`")?
+			}
+			MarkupErrorKind::TrailingBackslash => {
+				f.write_str("trailing backslash")?
+			}
+		}
+
+		write!(f, " at position {}", self.position)
+	}
+}
+
+impl Error for MarkupError {}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Node {
+	Text(String),
+	/// A `%s`, which is replaced by an argument
+	Placeholder,
+	/// Nodes styled with the chalk methods of the given names, on top of the
+	/// style surrounding them
+	Styled(Vec<String>, Vec<Node>),
+}
+
+/// Text with inline styling, parsed at runtime.
+///
+/// Styled text is written as `{styles text}`, where `styles` is a list of
+/// chalk method names separated by dots, such as `red.bold` or
+/// `bg_ansi(17).italic`. Styled text may be nested, in which case the inner
+/// styles are applied on top of the outer ones. A `%s` is replaced by the next
+/// argument given when rendering. Braces, backslashes, and percent signs can
+/// be written literally as `\{`, `\}`, `\\`, and `%%`.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{ColorLevel, Markup};
+///
+/// let markup: Markup = "{red.bold Error:} file {underline %s} not found"
+/// 	.parse()
+/// 	.unwrap();
+/// let text = markup.render_at(ColorLevel::Basic, &[&"notes.txt"]);
+/// assert_eq!(
+/// 	text,
+/// 	"\x1b[1;31mError:\x1b[22;39m file \x1b[4mnotes.txt\x1b[24m not found"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Markup {
+	nodes: Vec<Node>,
+}
+
+struct Parser<'a> {
+	source: &'a str,
+	position: usize,
+}
+
+impl Parser<'_> {
+	fn error(&self, kind: MarkupErrorKind, position: usize) -> MarkupError {
+		MarkupError { kind, position }
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.source[self.position..].chars().next()
+	}
+
+	fn next(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.position += c.len_utf8();
+		Some(c)
+	}
+
+	/// Parses nodes until the end of the group, or the end of the markup if
+	/// `open` is `None`
+	fn parse_nodes(
+		&mut self,
+		open: Option<usize>,
+	) -> Result<Vec<Node>, MarkupError> {
+		let mut nodes = Vec::new();
+		let mut text = String::new();
+
+		loop {
+			let start = self.position;
+			match self.next() {
+				None => match open {
+					Some(open) => {
+						return Err(self.error(MarkupErrorKind::Unclosed, open))
+					}
+					None => break,
+				},
+				Some('}') => match open {
+					Some(_) => break,
+					None => {
+						let kind = MarkupErrorKind::UnexpectedClose;
+						return Err(self.error(kind, start));
+					}
+				},
+				Some('\\') => match self.next() {
+					Some(c) => text.push(c),
+					None => {
+						let kind = MarkupErrorKind::TrailingBackslash;
+						return Err(self.error(kind, start));
+					}
+				},
+				Some('%') if self.peek() == Some('%') => {
+					self.next();
+					text.push('%');
+				}
+				Some('%') if self.peek() == Some('s') => {
+					self.next();
+					if !text.is_empty() {
+						nodes.push(Node::Text(std::mem::take(&mut text)));
+					}
+					nodes.push(Node::Placeholder);
+				}
+				Some('{') => {
+					if !text.is_empty() {
+						nodes.push(Node::Text(std::mem::take(&mut text)));
+					}
+					nodes.push(self.parse_group(start)?);
+				}
+				Some(c) => text.push(c),
+			}
+		}
+
+		if !text.is_empty() {
+			nodes.push(Node::Text(text));
+		}
+
+		Ok(nodes)
+	}
+
+	/// Parses a group, after its opening brace
+	fn parse_group(&mut self, open: usize) -> Result<Node, MarkupError> {
+		let rest = &self.source[self.position..];
+		let end = rest
+			.find(|c: char| c.is_whitespace() || c == '}')
+			.unwrap_or(rest.len());
+		if end == 0 {
+			return Err(self.error(MarkupErrorKind::MissingStyle, open));
+		}
+
+		let mut names = Vec::new();
+		let mut offset = self.position;
+		for name in rest[..end].split('.') {
+			if !apply_name(&mut Chalk::default(), name) {
+				let kind = MarkupErrorKind::UnknownStyle(name.to_string());
+				return Err(self.error(kind, offset));
+			}
+			names.push(name.to_string());
+			offset += name.len() + 1;
+		}
+
+		self.position += end;
+		if self.peek().is_some_and(char::is_whitespace) {
+			self.next();
+		}
+
+		let nodes = self.parse_nodes(Some(open))?;
+		Ok(Node::Styled(names, nodes))
+	}
+}
+
+impl Markup {
+	/// Parses a piece of markup
+	pub fn parse(source: &str) -> Result<Self, MarkupError> {
+		let mut parser = Parser {
+			source,
+			position: 0,
+		};
+		let nodes = parser.parse_nodes(None)?;
+		Ok(Markup { nodes })
+	}
+
+	fn collect_spans<'a>(
+		nodes: &[Node],
+		chalk: &Chalk,
+		args: &mut impl Iterator<Item = &'a &'a dyn Display>,
+		spans: &mut Vec<(Chalk, String)>,
+	) {
+		for node in nodes {
+			let text = match node {
+				Node::Text(text) => text.clone(),
+				Node::Placeholder => match args.next() {
+					Some(arg) => arg.to_string(),
+					None => "%s".to_string(),
+				},
+				Node::Styled(names, nodes) => {
+					let mut inner = *chalk;
+					for name in names {
+						apply_name(&mut inner, name);
+					}
+					Self::collect_spans(nodes, &inner, args, spans);
+					continue;
+				}
+			};
+
+			match spans.last_mut() {
+				Some((last, last_text)) if last == chalk => {
+					last_text.push_str(&text)
+				}
+				_ => spans.push((*chalk, text)),
+			}
+		}
+	}
+
+	/// Splits the markup into pieces of text and the chalk each one is styled
+	/// with, replacing each `%s` with the next argument.
+	///
+	/// If there are fewer arguments than placeholders, the remaining
+	/// placeholders are left as `%s`.
+	pub fn spans<'a>(
+		&self,
+		args: &'a [&'a dyn Display],
+	) -> Vec<(Chalk, String)> {
+		let mut spans = Vec::new();
+		let mut args = args.iter();
+		Self::collect_spans(
+			&self.nodes,
+			&Chalk::default(),
+			&mut args,
+			&mut spans,
+		);
+		spans
+	}
+
+	/// Renders the markup at the given color level
+	pub fn render_at(
+		&self,
+		level: ColorLevel,
+		args: &[&dyn Display],
+	) -> String {
+		let mut output = String::new();
+		for (chalk, text) in self.spans(args) {
+			// writing to a string can't fail
+			let _ = write!(output, "{}", chalk.paint(text).at_level(level));
+		}
+
+		output
+	}
+
+	/// Renders the markup at the color level of standard output
+	pub fn render(&self, args: &[&dyn Display]) -> String {
+		self.render_at(ColorLevel::stdout(), args)
+	}
+}
+
+impl FromStr for Markup {
+	type Err = MarkupError;
+
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		Self::parse(source)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn nested_styles() {
+		let markup = Markup::parse(r"{bold a {red b \{\}} c} 100%%").unwrap();
+		let bold = Chalk::new().with_bold();
+		let bold_red = Chalk::new().with_bold().with_red();
+		assert_eq!(
+			markup.spans(&[]),
+			[
+				(bold, "a ".to_string()),
+				(bold_red, "b {}".to_string()),
+				(bold, " c".to_string()),
+				(Chalk::new(), " 100%".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn errors() {
+		let error = |source| Markup::parse(source).unwrap_err();
+		assert_eq!(
+			error("ok {bold.purple x}").kind(),
+			&MarkupErrorKind::UnknownStyle("purple".to_string())
+		);
+		assert_eq!(error("ok {bold.purple x}").position(), 9);
+		assert_eq!(error("a {red b").kind(), &MarkupErrorKind::Unclosed);
+		assert_eq!(error("a {red b").position(), 2);
+		assert_eq!(error("a}").kind(), &MarkupErrorKind::UnexpectedClose);
+		assert_eq!(error("{ x}").kind(), &MarkupErrorKind::MissingStyle);
+	}
+}
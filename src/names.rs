@@ -0,0 +1,123 @@
+use crate::Chalk;
+
+type Setter = fn(&mut Chalk) -> &mut Chalk;
+
+/// The chalk methods which can be referred to by name
+const SETTERS: [(&str, Setter); 70] = [
+	("default_color", Chalk::default_color),
+	("black", Chalk::black),
+	("red", Chalk::red),
+	("green", Chalk::green),
+	("yellow", Chalk::yellow),
+	("blue", Chalk::blue),
+	("magenta", Chalk::magenta),
+	("cyan", Chalk::cyan),
+	("light_gray", Chalk::light_gray),
+	("light_grey", Chalk::light_grey),
+	("gray", Chalk::gray),
+	("grey", Chalk::grey),
+	("light_black", Chalk::light_black),
+	("light_red", Chalk::light_red),
+	("light_green", Chalk::light_green),
+	("light_yellow", Chalk::light_yellow),
+	("light_blue", Chalk::light_blue),
+	("light_magenta", Chalk::light_magenta),
+	("light_cyan", Chalk::light_cyan),
+	("white", Chalk::white),
+	("default_background", Chalk::default_background),
+	("bg_black", Chalk::bg_black),
+	("bg_red", Chalk::bg_red),
+	("bg_green", Chalk::bg_green),
+	("bg_yellow", Chalk::bg_yellow),
+	("bg_blue", Chalk::bg_blue),
+	("bg_magenta", Chalk::bg_magenta),
+	("bg_cyan", Chalk::bg_cyan),
+	("bg_light_gray", Chalk::bg_light_gray),
+	("bg_light_grey", Chalk::bg_light_grey),
+	("bg_gray", Chalk::bg_gray),
+	("bg_grey", Chalk::bg_grey),
+	("bg_light_black", Chalk::bg_light_black),
+	("bg_light_red", Chalk::bg_light_red),
+	("bg_light_green", Chalk::bg_light_green),
+	("bg_light_yellow", Chalk::bg_light_yellow),
+	("bg_light_blue", Chalk::bg_light_blue),
+	("bg_light_magenta", Chalk::bg_light_magenta),
+	("bg_light_cyan", Chalk::bg_light_cyan),
+	("bg_white", Chalk::bg_white),
+	("reset_style", Chalk::reset_style),
+	("reset_weight", Chalk::reset_weight),
+	("bold", Chalk::bold),
+	("dim", Chalk::dim),
+	("italic", Chalk::italic),
+	("unitalic", Chalk::unitalic),
+	("no_underline", Chalk::no_underline),
+	("underline", Chalk::underline),
+	("double_underline", Chalk::double_underline),
+	("curly_underline", Chalk::curly_underline),
+	("dotted_underline", Chalk::dotted_underline),
+	("dashed_underline", Chalk::dashed_underline),
+	("default_underline_color", Chalk::default_underline_color),
+	("stop_blink", Chalk::stop_blink),
+	("blink", Chalk::blink),
+	("rapid_blink", Chalk::rapid_blink),
+	("invert", Chalk::invert),
+	("uninvert", Chalk::uninvert),
+	("hide", Chalk::hide),
+	("unhide", Chalk::unhide),
+	("strikethrough", Chalk::strikethrough),
+	("no_strikethrough", Chalk::no_strikethrough),
+	("overline", Chalk::overline),
+	("no_overline", Chalk::no_overline),
+	("frame", Chalk::frame),
+	("encircle", Chalk::encircle),
+	("no_frame", Chalk::no_frame),
+	("superscript", Chalk::superscript),
+	("subscript", Chalk::subscript),
+	("no_script", Chalk::no_script),
+];
+
+/// Parses the arguments of a name like `rgb(1,2,3)`
+fn parse_args<const N: usize>(args: &str) -> Option<[u8; N]> {
+	let mut values = [0; N];
+	let mut args = args.split(',');
+	for value in values.iter_mut() {
+		*value = args.next()?.trim().parse().ok()?;
+	}
+
+	match args.next() {
+		Some(_) => None,
+		None => Some(values),
+	}
+}
+
+/// Applies the chalk method with the given name.
+///
+/// The name may be any color or style method which doesn't take arguments,
+/// such as `light_red`, `bg_blue`, or `bold`, or one of `ansi(n)`,
+/// `bg_ansi(n)`, `underline_ansi(n)`, `rgb(r,g,b)`, `bg_rgb(r,g,b)`, and
+/// `underline_rgb(r,g,b)`. Returns `false` if the name isn't recognized.
+pub(crate) fn apply_name(chalk: &mut Chalk, name: &str) -> bool {
+	if let Some((_, setter)) = SETTERS.iter().find(|(n, _)| *n == name) {
+		setter(chalk);
+		return true;
+	}
+
+	let Some((function, args)) =
+		name.strip_suffix(')').and_then(|name| name.split_once('('))
+	else {
+		return false;
+	};
+
+	match function {
+		"ansi" => parse_args(args).map(|[n]| chalk.ansi(n)),
+		"bg_ansi" => parse_args(args).map(|[n]| chalk.bg_ansi(n)),
+		"underline_ansi" => parse_args(args).map(|[n]| chalk.underline_ansi(n)),
+		"rgb" => parse_args(args).map(|[r, g, b]| chalk.rgb(r, g, b)),
+		"bg_rgb" => parse_args(args).map(|[r, g, b]| chalk.bg_rgb(r, g, b)),
+		"underline_rgb" => {
+			parse_args(args).map(|[r, g, b]| chalk.underline_rgb(r, g, b))
+		}
+		_ => None,
+	}
+	.is_some()
+}
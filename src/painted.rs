@@ -0,0 +1,412 @@
+use crate::hyperlink::Hyperlink;
+use crate::{Chalk, ColorLevel, Stream};
+
+use std::fmt::{Display, Write};
+
+/// The SGR codes which turn off an attribute that a [`Chalk`] may have set
+const CLOSING_CODES: [u16; 14] =
+	[0, 22, 23, 24, 25, 27, 28, 29, 39, 49, 54, 55, 59, 75];
+
+/// A value which is styled by a [`Chalk`] when it is displayed.
+///
+/// Nothing is allocated when a `Painted` value is formatted. The chalk is
+/// worked out once, as a single escape sequence kept on the stack, and the
+/// value is written directly into the formatter. Nothing is written around
+/// the value if the chalk doesn't set anything. Any width or precision given
+/// to the formatter applies to the value itself.
+///
+/// Values may contain text which was styled by another chalk. When that text
+/// turns off an attribute or resets every attribute, the style of the outer
+/// value is restored afterwards. This isn't done if a width or precision is
+/// given, since the value has to be padded by its own [`Display`] impl.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{Chalk, ColorLevel};
+///
+/// let mut chalk = Chalk::new();
+/// let name = chalk.cyan().paint("src/lib.rs").at_level(ColorLevel::Basic);
+/// let text = format!("[{:<12}]", name);
+/// assert_eq!(text, "[\x1b[36msrc/lib.rs  \x1b[39m]");
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Painted<T> {
+	chalk: Chalk,
+	value: T,
+	level: Option<ColorLevel>,
+	stream: Stream,
+	link: Option<Hyperlink>,
+	hyperlinks: Option<bool>,
+}
+
+impl<T> Painted<T> {
+	/// Styles a value with the given [`Chalk`]
+	#[inline(always)]
+	pub const fn new(chalk: Chalk, value: T) -> Self {
+		Painted {
+			chalk,
+			value,
+			level: None,
+			stream: Stream::Stdout,
+			link: None,
+			hyperlinks: None,
+		}
+	}
+
+	/// Renders the value at the given color level, instead of the level of
+	/// the stream it's written to
+	#[inline(always)]
+	pub fn at_level(mut self, level: ColorLevel) -> Self {
+		self.level = Some(level);
+		self
+	}
+
+	/// Renders the value for a stream other than standard output. Its color
+	/// level, and whether it shows hyperlinks, are used instead.
+	#[inline(always)]
+	pub fn to_stream(mut self, stream: Stream) -> Self {
+		self.stream = stream;
+		self
+	}
+
+	/// The color level the value will be rendered at
+	#[inline(always)]
+	pub fn level(&self) -> ColorLevel {
+		self.level
+			.unwrap_or_else(|| ColorLevel::detect(self.stream))
+	}
+
+	/// Makes the value a hyperlink to a URL, using OSC 8. This replaces any
+	/// link set by [`Chalk::link`], and unlike it, takes a URL which is made
+	/// at runtime.
+	///
+	/// If the terminal isn't known to show hyperlinks, or color is turned
+	/// off, the value is written without the link. Use [`crate::file_url`]
+	/// to link to a local file.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel};
+	///
+	/// let mut chalk = Chalk::new();
+	/// let text = chalk
+	/// 	.underline()
+	/// 	.paint("docs")
+	/// 	.link("https://example.com")
+	/// 	.at_level(ColorLevel::Basic);
+	/// assert_eq!(
+	/// 	text.clone().hyperlinks(true).to_string(),
+	/// 	"\x1b[4m\x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\\x1b[24m"
+	/// );
+	/// assert_eq!(text.hyperlinks(false).to_string(), "\x1b[4mdocs\x1b[24m");
+	/// ```
+	pub fn link(mut self, url: &str) -> Self {
+		self.link = Some(Hyperlink::new(url, None));
+		self
+	}
+
+	/// Makes the value a hyperlink with an id. Terminals highlight every
+	/// link with the same id and URL together, such as a link which is
+	/// split across several lines. The id can't contain `:` or `;`, so they
+	/// are removed.
+	pub fn link_with_id(mut self, url: &str, id: &str) -> Self {
+		self.link = Some(Hyperlink::new(url, Some(id)));
+		self
+	}
+
+	/// The URL the value links to, if it's a hyperlink
+	#[inline(always)]
+	pub fn url(&self) -> Option<&str> {
+		self.link
+			.as_ref()
+			.map(Hyperlink::url)
+			.or_else(|| self.chalk.url())
+	}
+
+	/// The link to write around the value, if any
+	#[inline(always)]
+	fn hyperlink(&self) -> Option<Hyperlink<&str>> {
+		self.link
+			.as_ref()
+			.map(Hyperlink::borrow)
+			.or(self.chalk.link)
+	}
+
+	/// Sets whether the hyperlink is written, instead of checking whether
+	/// the stream shows hyperlinks. Links are never written at
+	/// [`ColorLevel::None`].
+	#[inline(always)]
+	pub fn hyperlinks(mut self, enabled: bool) -> Self {
+		self.hyperlinks = Some(enabled);
+		self
+	}
+
+	/// Whether the hyperlink will be written, if there is one
+	pub fn shows_hyperlinks(&self) -> bool {
+		self.level().has_color()
+			&& self
+				.hyperlinks
+				.unwrap_or_else(|| self.stream.supports_hyperlinks())
+	}
+
+	/// The chalk used to style the value
+	#[inline(always)]
+	pub const fn chalk(&self) -> &Chalk {
+		&self.chalk
+	}
+
+	/// The value being styled
+	#[inline(always)]
+	pub const fn get_ref(&self) -> &T {
+		&self.value
+	}
+
+	/// Removes the styling from the value
+	#[inline(always)]
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+}
+
+impl<T: Display> Display for Painted<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let level = self.level();
+		if !level.has_color()
+			|| (self.chalk == Chalk::new() && self.link.is_none())
+		{
+			return Display::fmt(&self.value, f);
+		}
+
+		let link = self.hyperlink().filter(|_| self.shows_hyperlinks());
+		let prefix = self.chalk.prefix(level);
+		let prefix = prefix.as_str();
+		f.write_str(prefix)?;
+		if let Some(link) = link {
+			link.write_open(f)?;
+		}
+		if prefix.is_empty() || f.width().is_some() || f.precision().is_some() {
+			Display::fmt(&self.value, f)?;
+		} else {
+			let mut restore = Restore::new(f, prefix);
+			write!(restore, "{}", self.value)?;
+		}
+		if link.is_some() {
+			Hyperlink::write_close(f)?;
+		}
+		self.chalk.write_suffix(f, level)
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+	Text,
+	Escape,
+	Sgr,
+	/// Part of a sequence which isn't SGR
+	Other,
+}
+
+/// Passes text through to a writer, writing the prefix of a chalk again
+/// after every SGR sequence which turns off one of its attributes
+struct Restore<'a, W: ?Sized> {
+	inner: &'a mut W,
+	prefix: &'a str,
+	state: State,
+	/// The parameter currently being read
+	param: u16,
+	/// How many parameters are left in an extended color
+	skip: u8,
+	/// Whether the last parameter was 38, 48, or 58
+	extended: bool,
+	closes: bool,
+}
+
+impl<'a, W: Write + ?Sized> Restore<'a, W> {
+	fn new(inner: &'a mut W, prefix: &'a str) -> Self {
+		Restore {
+			inner,
+			prefix,
+			state: State::Text,
+			param: 0,
+			skip: 0,
+			extended: false,
+			closes: false,
+		}
+	}
+
+	/// Handles the end of an SGR parameter
+	fn end_param(&mut self) {
+		if self.extended {
+			self.extended = false;
+			self.skip = match self.param {
+				5 => 1,
+				2 => 3,
+				_ => 0,
+			};
+		} else if self.skip > 0 {
+			self.skip -= 1;
+		} else if matches!(self.param, 38 | 48 | 58) {
+			self.extended = true;
+		} else if CLOSING_CODES.contains(&self.param) {
+			self.closes = true;
+		}
+
+		self.param = 0;
+	}
+
+	/// Updates the state with a byte, returning true if the prefix needs to
+	/// be written after it
+	fn step(&mut self, byte: u8) -> bool {
+		match (self.state, byte) {
+			(_, 0x1b) => self.state = State::Escape,
+			(State::Text, _) => (),
+			(State::Escape, b'[') => {
+				self.state = State::Sgr;
+				self.param = 0;
+				self.skip = 0;
+				self.extended = false;
+				self.closes = false;
+			}
+			(State::Escape, _) => self.state = State::Text,
+			(State::Sgr, b'0'..=b'9') => {
+				let digit = (byte - b'0') as u16;
+				self.param =
+					self.param.saturating_mul(10).saturating_add(digit);
+			}
+			(State::Sgr, b';') | (State::Sgr, b':') => self.end_param(),
+			(State::Sgr, b'm') => {
+				self.end_param();
+				self.state = State::Text;
+				return self.closes;
+			}
+			(State::Sgr, 0x40..=0x7e) | (State::Other, 0x40..=0x7e) => {
+				self.state = State::Text
+			}
+			(State::Sgr, _) | (State::Other, _) => self.state = State::Other,
+		}
+
+		false
+	}
+}
+
+impl<W: Write + ?Sized> Write for Restore<'_, W> {
+	fn write_str(&mut self, s: &str) -> std::fmt::Result {
+		let mut start = 0;
+		for (i, byte) in s.bytes().enumerate() {
+			if self.step(byte) {
+				self.inner.write_str(&s[start..=i])?;
+				self.inner.write_str(self.prefix)?;
+				start = i + 1;
+			}
+		}
+
+		self.inner.write_str(&s[start..])
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	const PREFIX: &str = "\x1b[1;31m";
+
+	fn restore(text: &str) -> String {
+		let mut out = String::new();
+		let mut restore = Restore::new(&mut out, PREFIX);
+		restore.write_str(text).unwrap();
+		out
+	}
+
+	#[test]
+	fn restores_after_closes() {
+		assert_eq!(restore("a\x1b[0mb"), "a\x1b[0m\x1b[1;31mb");
+		assert_eq!(restore("a\x1b[mb"), "a\x1b[m\x1b[1;31mb");
+		assert_eq!(restore("\x1b[22m"), "\x1b[22m\x1b[1;31m");
+		assert_eq!(restore("\x1b[4;39m"), "\x1b[4;39m\x1b[1;31m");
+		assert_eq!(restore("\x1b[1;32m"), "\x1b[1;32m");
+	}
+
+	#[test]
+	fn restores_across_writes() {
+		let mut out = String::new();
+		let mut restore = Restore::new(&mut out, PREFIX);
+		restore.write_str("a\x1b[3").unwrap();
+		restore.write_str("9mb").unwrap();
+		assert_eq!(out, "a\x1b[39m\x1b[1;31mb");
+	}
+
+	#[test]
+	fn skips_extended_colors() {
+		assert_eq!(restore("\x1b[38;5;39m"), "\x1b[38;5;39m");
+		assert_eq!(restore("\x1b[48;5;0m"), "\x1b[48;5;0m");
+		assert_eq!(restore("\x1b[38:2:0:22:39m"), "\x1b[38:2:0:22:39m");
+		assert_eq!(restore("\x1b[38;2;0;22;39m"), "\x1b[38;2;0;22;39m");
+		assert_eq!(restore("\x1b[58;5;24;0m"), "\x1b[58;5;24;0m\x1b[1;31m");
+	}
+
+	#[test]
+	fn passes_other_sequences() {
+		assert_eq!(restore("\x1b[0K"), "\x1b[0K");
+		assert_eq!(restore("\x1b[?25l"), "\x1b[?25l");
+		assert_eq!(
+			restore("\x1b]8;;https://0.0.0.0\x1b\\a\x1b]8;;\x1b\\"),
+			"\x1b]8;;https://0.0.0.0\x1b\\a\x1b]8;;\x1b\\"
+		);
+		assert_eq!(restore("\x1b(0"), "\x1b(0");
+	}
+
+	#[test]
+	fn bypasses_padding() {
+		let painted = Chalk::new()
+			.with_red()
+			.paint("a\x1b[0mb")
+			.at_level(ColorLevel::Basic);
+		assert_eq!(format!("{}", painted), "\x1b[31ma\x1b[0m\x1b[31mb\x1b[39m");
+		assert_eq!(format!("{:>8}", painted), "\x1b[31m  a\x1b[0mb\x1b[39m");
+		assert_eq!(format!("{:.1}", painted), "\x1b[31ma\x1b[39m");
+	}
+
+	#[test]
+	fn links() {
+		const LINKED: Chalk = Chalk::new().with_link("https://a.b");
+		let painted = LINKED.paint("a").at_level(ColorLevel::Basic);
+		assert_eq!(painted.url(), Some("https://a.b"));
+		assert_eq!(
+			painted.clone().hyperlinks(true).to_string(),
+			"\x1b]8;;https://a.b\x1b\\a\x1b]8;;\x1b\\"
+		);
+		assert_eq!(painted.clone().hyperlinks(false).to_string(), "a");
+
+		let painted = painted.link_with_id("https://c.d", "e").hyperlinks(true);
+		assert_eq!(painted.url(), Some("https://c.d"));
+		assert_eq!(
+			painted.to_string(),
+			"\x1b]8;id=e;https://c.d\x1b\\a\x1b]8;;\x1b\\"
+		);
+
+		let mut chalk = LINKED;
+		chalk.layer(&Chalk::new().with_red());
+		assert_eq!(chalk.url(), Some("https://a.b"));
+		assert_eq!(chalk.unlink().url(), None);
+	}
+
+	#[test]
+	fn streams() {
+		let painted = Chalk::new().with_link("https://a.b").paint("a");
+		let painted = painted.to_stream(Stream::Stderr);
+		ColorLevel::set_override(Stream::Stderr, Some(ColorLevel::Basic));
+		Stream::Stderr.set_hyperlinks_override(Some(true));
+		assert_eq!(painted.level(), ColorLevel::Basic);
+		assert!(painted.shows_hyperlinks());
+		Stream::Stderr.set_hyperlinks_override(Some(false));
+		assert!(!painted.shows_hyperlinks());
+		ColorLevel::set_override(Stream::Stderr, Some(ColorLevel::None));
+		assert_eq!(painted.to_string(), "a");
+
+		ColorLevel::set_override(Stream::Stderr, None);
+		Stream::Stderr.set_hyperlinks_override(None);
+	}
+}
@@ -0,0 +1,135 @@
+use crate::{AnsiColor, BasicColor, ChalkType, RgbColor, Terminal};
+
+use std::sync::OnceLock;
+
+/// The colors a terminal shows for each color in the 256 color palette, and
+/// for text which doesn't have a color set.
+///
+/// The default palette is xterm's.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{BasicColor, Palette, RgbColor};
+///
+/// let mut palette = Palette::default();
+/// palette.set_basic(BasicColor::Red, RgbColor::new(205, 49, 49));
+/// assert_eq!(palette.get(1), RgbColor::new(205, 49, 49));
+/// assert_eq!(palette.get(196), RgbColor::new(255, 0, 0));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Palette {
+	colors: [RgbColor; 256],
+	foreground: RgbColor,
+	background: RgbColor,
+}
+
+impl Default for Palette {
+	fn default() -> Self {
+		Self::xterm()
+	}
+}
+
+impl Palette {
+	/// The palette of xterm, with light gray text on a black background
+	pub fn xterm() -> Self {
+		let mut colors = [RgbColor::new(0, 0, 0); 256];
+		for (i, color) in colors.iter_mut().enumerate() {
+			*color = AnsiColor::from_num(i as u8).to_rgb();
+		}
+
+		Palette {
+			colors,
+			foreground: colors[7],
+			background: colors[0],
+		}
+	}
+
+	/// Gets the palette of the terminal which controls this process.
+	///
+	/// The terminal is only asked for its colors the first time this is
+	/// called. If it can't be asked, or it doesn't reply, xterm's palette is
+	/// used instead.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{BasicColor, Palette};
+	///
+	/// let red = Palette::detect().get(BasicColor::Red.index());
+	/// println!("red looks like {}", red.to_hex());
+	/// ```
+	pub fn detect() -> &'static Palette {
+		static DETECTED: OnceLock<Palette> = OnceLock::new();
+		DETECTED.get_or_init(|| {
+			Terminal::open()
+				.and_then(|mut terminal| terminal.palette())
+				.unwrap_or_default()
+		})
+	}
+
+	/// Gets the color with the given index
+	#[inline(always)]
+	pub const fn get(&self, index: u8) -> RgbColor {
+		self.colors[index as usize]
+	}
+
+	/// Sets the color with the given index
+	pub fn set(&mut self, index: u8, color: RgbColor) -> &mut Self {
+		self.colors[index as usize] = color;
+		self
+	}
+
+	/// Sets the color shown for a basic color
+	pub fn set_basic(
+		&mut self,
+		basic: BasicColor,
+		color: RgbColor,
+	) -> &mut Self {
+		self.set(basic.index(), color)
+	}
+
+	/// The color of text which doesn't have a foreground color
+	#[inline(always)]
+	pub const fn foreground(&self) -> RgbColor {
+		self.foreground
+	}
+
+	/// The color behind text which doesn't have a background color
+	#[inline(always)]
+	pub const fn background(&self) -> RgbColor {
+		self.background
+	}
+
+	/// Sets the color of text which doesn't have a foreground color
+	pub fn set_foreground(&mut self, color: RgbColor) -> &mut Self {
+		self.foreground = color;
+		self
+	}
+
+	/// Sets the color behind text which doesn't have a background color
+	pub fn set_background(&mut self, color: RgbColor) -> &mut Self {
+		self.background = color;
+		self
+	}
+
+	/// Changes every color in the palette
+	pub(crate) fn map(&self, map: impl Fn(RgbColor) -> RgbColor) -> Palette {
+		let mut palette = self.clone();
+		for color in palette.colors.iter_mut() {
+			*color = map(*color);
+		}
+		palette.foreground = map(self.foreground);
+		palette.background = map(self.background);
+		palette
+	}
+
+	/// Gets the color shown for a chalk color, or `None` if it isn't set
+	pub(crate) fn resolve(&self, color: &ChalkType) -> Option<RgbColor> {
+		match color {
+			ChalkType::Default => None,
+			ChalkType::Rgb(color) => Some(*color),
+			color => color.index().map(|i| self.get(i)),
+		}
+	}
+}
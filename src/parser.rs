@@ -0,0 +1,525 @@
+use crate::basic_chalk::BasicColor;
+use crate::{Chalk, ChalkType};
+
+use std::error::Error;
+use std::fmt::Display;
+
+/// A piece of escaped text, as read by an [`SgrParser`]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Segment {
+	/// Text, along with the style it was written in
+	Text(Chalk, String),
+	/// An SGR parameter which can't be represented by a [`Chalk`], such as
+	/// `26` or `60`. Any sub-parameters are included, separated by `:`.
+	Unknown(String),
+	/// An escape sequence other than SGR, such as cursor movement, exactly as
+	/// it appeared in the input
+	Control(String),
+}
+
+/// An SGR parameter which couldn't be applied to a [`Chalk`]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SgrError {
+	param: String,
+}
+
+impl SgrError {
+	/// The parameter which couldn't be applied
+	pub fn param(&self) -> &str {
+		&self.param
+	}
+}
+
+impl Display for SgrError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "unsupported SGR parameter `{}`", self.param)
+	}
+}
+
+impl Error for SgrError {}
+
+/// Parses an SGR color which was introduced by 38 or 48.
+///
+/// The color may be given with sub-parameters, as in `38:5:200`, or as the
+/// parameters after it, as in `38;5;200`. Returns the color and the number of
+/// parameters that were used, or `None` if the color is malformed.
+fn parse_extended(group: &[&str], rest: &[&str]) -> Option<(ChalkType, usize)> {
+	let num = |s: &&str| s.parse::<u8>().ok();
+	if group.len() > 1 {
+		let color = match (group[1], &group[2..]) {
+			("5", [n]) => ChalkType::ansi(num(n)?),
+			// the color space id is optional
+			("2", [_, r, g, b]) | ("2", [r, g, b]) => {
+				ChalkType::rgb(num(r)?, num(g)?, num(b)?)
+			}
+			_ => return None,
+		};
+		return Some((color, 0));
+	}
+
+	match rest {
+		["5", n, ..] => Some((ChalkType::ansi(num(n)?), 2)),
+		["2", r, g, b, ..] => {
+			Some((ChalkType::rgb(num(r)?, num(g)?, num(b)?), 4))
+		}
+		_ => None,
+	}
+}
+
+impl Chalk {
+	/// Applies a single SGR parameter group. Returns `false` if the parameter
+	/// isn't supported.
+	fn apply_sgr_param(&mut self, code: u8) -> bool {
+		match code {
+			0 => {
+				*self = Chalk::default();
+			}
+			1 => {
+				self.bold();
+			}
+			2 => {
+				self.dim();
+			}
+			3 => {
+				self.italic();
+			}
+			4 => {
+				self.underline();
+			}
+			5 => {
+				self.blink();
+			}
+			6 => {
+				self.rapid_blink();
+			}
+			7 => {
+				self.invert();
+			}
+			8 => {
+				self.hide();
+			}
+			9 => {
+				self.strikethrough();
+			}
+			21 => {
+				self.double_underline();
+			}
+			22 => {
+				self.reset_weight();
+			}
+			23 => {
+				self.unitalic();
+			}
+			24 => {
+				self.no_underline();
+			}
+			25 => {
+				self.stop_blink();
+			}
+			27 => {
+				self.uninvert();
+			}
+			28 => {
+				self.unhide();
+			}
+			29 => {
+				self.no_strikethrough();
+			}
+			30..=37 => {
+				let color = BasicColor::from_index(code - 30);
+				self.foreground = ChalkType::Basic(color);
+			}
+			39 => {
+				self.default_color();
+			}
+			40..=47 => {
+				let color = BasicColor::from_index(code - 40);
+				self.background = ChalkType::Basic(color);
+			}
+			49 => {
+				self.default_background();
+			}
+			51 => {
+				self.frame();
+			}
+			52 => {
+				self.encircle();
+			}
+			53 => {
+				self.overline();
+			}
+			54 => {
+				self.no_frame();
+			}
+			55 => {
+				self.no_overline();
+			}
+			59 => {
+				self.default_underline_color();
+			}
+			73 => {
+				self.superscript();
+			}
+			74 => {
+				self.subscript();
+			}
+			75 => {
+				self.no_script();
+			}
+			90..=97 => {
+				let color = BasicColor::from_index(code - 82);
+				self.foreground = ChalkType::Basic(color);
+			}
+			100..=107 => {
+				let color = BasicColor::from_index(code - 92);
+				self.background = ChalkType::Basic(color);
+			}
+			_ => return false,
+		}
+
+		true
+	}
+
+	/// Applies an underline style, given as a sub-parameter of 4. Returns
+	/// `false` if the style isn't supported.
+	fn apply_underline_style(&mut self, style: &str) -> bool {
+		match style {
+			"0" => self.no_underline(),
+			"1" => self.underline(),
+			"2" => self.double_underline(),
+			"3" => self.curly_underline(),
+			"4" => self.dotted_underline(),
+			"5" => self.dashed_underline(),
+			_ => return false,
+		};
+
+		true
+	}
+
+	/// Applies the parameters of an SGR sequence to the chalk, calling
+	/// `unknown` for each parameter which isn't supported.
+	pub(crate) fn apply_sgr(
+		&mut self,
+		params: &str,
+		mut unknown: impl FnMut(&str),
+	) {
+		let groups: Vec<&str> = params.split(';').collect();
+		let mut i = 0;
+		while i < groups.len() {
+			let group = groups[i];
+			let parts: Vec<&str> = group.split(':').collect();
+			i += 1;
+
+			let code = if parts[0].is_empty() {
+				Some(0)
+			} else {
+				parts[0].parse::<u8>().ok()
+			};
+
+			match code {
+				Some(code @ 38) | Some(code @ 48) | Some(code @ 58) => {
+					match parse_extended(&parts, &groups[i..]) {
+						Some((color, used)) => {
+							i += used;
+							match code {
+								38 => self.foreground = color,
+								48 => self.background = color,
+								_ => self.underline_color = color,
+							}
+						}
+						None => {
+							unknown(group);
+							// the rest of the sequence can't be trusted
+							return;
+						}
+					}
+				}
+				Some(code) if parts.len() == 1 => {
+					if !self.apply_sgr_param(code) {
+						unknown(group);
+					}
+				}
+				Some(4) if parts.len() == 2 => {
+					if !self.apply_underline_style(parts[1]) {
+						unknown(group);
+					}
+				}
+				_ => unknown(group),
+			}
+		}
+	}
+
+	/// Creates a chalk from the parameters of an SGR escape sequence.
+	///
+	/// The parameters are the part of the sequence between `\x1b[` and `m`.
+	/// An error is returned if any of the parameters can't be represented by
+	/// a chalk.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let chalk = Chalk::from_sgr("1;31;48;5;200").unwrap();
+	/// assert!(chalk.is_bold());
+	/// assert_eq!(chalk, Chalk::new().bold().red().bg_ansi(200).clone());
+	/// assert!(Chalk::from_sgr("1;60").is_err());
+	/// ```
+	pub fn from_sgr(params: &str) -> Result<Chalk, SgrError> {
+		let mut chalk = Chalk::default();
+		let mut error = None;
+		chalk.apply_sgr(params, |param| {
+			error.get_or_insert_with(|| SgrError {
+				param: param.to_string(),
+			});
+		});
+
+		match error {
+			Some(error) => Err(error),
+			None => Ok(chalk),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum State {
+	#[default]
+	Text,
+	/// An escape character was read
+	Escape,
+	/// Reading a control sequence
+	Csi,
+	/// Reading a string which ends with BEL or ST
+	String,
+	/// An escape character was read inside of a string
+	StringEscape,
+	/// Reading the intermediate bytes of an escape sequence
+	Intermediate,
+}
+
+/// Turns escaped text back into styled segments.
+///
+/// Input may be given in chunks of any size, such as the output of a child
+/// process as it is read. Escape sequences and UTF-8 characters which are
+/// split between chunks are kept until the rest of them arrives.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{Chalk, Segment, SgrParser};
+///
+/// let mut parser = SgrParser::new();
+/// let mut segments = parser.feed(b"plain \x1b[1;3");
+/// segments.extend(parser.feed(b"1mbold red\x1b[m"));
+///
+/// let bold_red = Chalk::new().bold().red().clone();
+/// assert_eq!(
+/// 	segments,
+/// 	[
+/// 		Segment::Text(Chalk::new(), "plain ".to_string()),
+/// 		Segment::Text(bold_red, "bold red".to_string()),
+/// 	]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SgrParser {
+	chalk: Chalk,
+	state: State,
+	/// Text which hasn't been returned yet
+	text: Vec<u8>,
+	/// The escape sequence currently being read
+	sequence: Vec<u8>,
+}
+
+impl SgrParser {
+	/// Creates a parser which starts out with the default style
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The style that text is currently being read in
+	pub fn chalk(&self) -> &Chalk {
+		&self.chalk
+	}
+
+	/// Moves the text which has been read so far into a segment.
+	///
+	/// If `partial` is true, an incomplete UTF-8 character at the end of the
+	/// text is kept for the next chunk.
+	fn flush_text(&mut self, segments: &mut Vec<Segment>, partial: bool) {
+		let keep = match std::str::from_utf8(&self.text) {
+			Err(e) if partial && e.error_len().is_none() => {
+				self.text.len() - e.valid_up_to()
+			}
+			_ => 0,
+		};
+
+		let end = self.text.len() - keep;
+		if end == 0 {
+			return;
+		}
+
+		let text = String::from_utf8_lossy(&self.text[..end]).into_owned();
+		self.text.drain(..end);
+		match segments.last_mut() {
+			Some(Segment::Text(chalk, last)) if *chalk == self.chalk => {
+				last.push_str(&text)
+			}
+			_ => segments.push(Segment::Text(self.chalk, text)),
+		}
+	}
+
+	/// Handles the end of an escape sequence
+	fn finish_sequence(&mut self, segments: &mut Vec<Segment>) {
+		self.state = State::Text;
+		let sequence = std::mem::take(&mut self.sequence);
+		let sequence = String::from_utf8_lossy(&sequence);
+
+		let params = sequence
+			.strip_prefix("\x1b[")
+			.and_then(|s| s.strip_suffix('m'));
+		match params {
+			Some(params) => self.chalk.apply_sgr(params, |param| {
+				segments.push(Segment::Unknown(param.to_string()))
+			}),
+			None => segments.push(Segment::Control(sequence.into_owned())),
+		}
+	}
+
+	/// Reads a chunk of input, returning the segments which were completed
+	pub fn feed(&mut self, input: &[u8]) -> Vec<Segment> {
+		let mut segments = Vec::new();
+		for &byte in input {
+			match self.state {
+				State::Text if byte == 0x1b => {
+					self.flush_text(&mut segments, false);
+					self.sequence.push(byte);
+					self.state = State::Escape;
+				}
+				State::Text => self.text.push(byte),
+				State::Escape => {
+					self.sequence.push(byte);
+					self.state = match byte {
+						b'[' => State::Csi,
+						b']' | b'P' | b'X' | b'^' | b'_' => State::String,
+						0x20..=0x2f => State::Intermediate,
+						_ => {
+							self.finish_sequence(&mut segments);
+							State::Text
+						}
+					};
+				}
+				State::Csi | State::Intermediate => {
+					self.sequence.push(byte);
+					let done = match self.state {
+						State::Csi => (0x40..=0x7e).contains(&byte),
+						_ => !(0x20..=0x2f).contains(&byte),
+					};
+					if done {
+						self.finish_sequence(&mut segments);
+					}
+				}
+				State::String | State::StringEscape => {
+					self.sequence.push(byte);
+					if byte == 0x07
+						|| (self.state == State::StringEscape && byte == b'\\')
+					{
+						self.finish_sequence(&mut segments);
+					} else if byte == 0x1b {
+						self.state = State::StringEscape;
+					} else {
+						self.state = State::String;
+					}
+				}
+			}
+		}
+
+		self.flush_text(&mut segments, true);
+		segments
+	}
+
+	/// Finishes parsing, returning any text that was left over.
+	///
+	/// An incomplete escape sequence at the end of the input is returned as a
+	/// control segment.
+	pub fn finish(mut self) -> Vec<Segment> {
+		let mut segments = Vec::new();
+		self.flush_text(&mut segments, false);
+		if !self.sequence.is_empty() {
+			let sequence = String::from_utf8_lossy(&self.sequence);
+			segments.push(Segment::Control(sequence.into_owned()));
+		}
+
+		segments
+	}
+}
+
+/// Parses a complete piece of escaped text into styled segments
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{parse_ansi, Chalk, Segment};
+///
+/// let segments = parse_ansi("\x1b[60;32mok\x1b[2K");
+/// assert_eq!(
+/// 	segments,
+/// 	[
+/// 		Segment::Unknown("60".to_string()),
+/// 		Segment::Text(Chalk::new().green().clone(), "ok".to_string()),
+/// 		Segment::Control("\x1b[2K".to_string()),
+/// 	]
+/// );
+/// ```
+pub fn parse_ansi(text: &str) -> Vec<Segment> {
+	let mut parser = SgrParser::new();
+	let mut segments = parser.feed(text.as_bytes());
+	segments.extend(parser.finish());
+	segments
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+	use crate::ColorLevel;
+
+	#[test]
+	fn round_trips_chalk_output() {
+		let mut chalk = Chalk::new();
+		chalk
+			.rgb(1, 2, 3)
+			.bg_light_cyan()
+			.underline_rgb(4, 5, 6)
+			.bold()
+			.curly_underline()
+			.rapid_blink()
+			.invert()
+			.strikethrough()
+			.encircle()
+			.overline()
+			.subscript();
+		let text = chalk.string_at(ColorLevel::TrueColor, &"text");
+
+		let segments = parse_ansi(&text);
+		assert_eq!(segments, [Segment::Text(chalk, "text".to_string())]);
+	}
+
+	#[test]
+	fn split_input() {
+		let input = "\x1b[38:2::10:20:30mé\x1b]8;;url\x1b\\".as_bytes();
+		let mut parser = SgrParser::new();
+		let mut segments = Vec::new();
+		for byte in input {
+			segments.extend(parser.feed(std::slice::from_ref(byte)));
+		}
+
+		assert_eq!(
+			segments,
+			[
+				Segment::Text(Chalk::new().with_rgb(10, 20, 30), "é".into()),
+				Segment::Control("\x1b]8;;url\x1b\\".into()),
+			]
+		);
+	}
+}
@@ -0,0 +1,431 @@
+use crate::{Palette, RgbColor};
+
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::{
+	io::{Read, Write},
+	os::unix::io::AsRawFd,
+	time::Instant,
+};
+
+/// How long to wait for the terminal to reply by default
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Asks for the foreground and background colors
+const DYNAMIC_COLORS: &str = "\x1b]10;?\x1b\\\x1b]11;?\x1b\\";
+
+/// Asks the terminal for its primary device attributes. Every terminal
+/// replies to this, so it marks the end of the replies to the other queries.
+#[cfg(unix)]
+const DEVICE_ATTRIBUTES: &str = "\x1b[c";
+
+/// A connection to a terminal, which can be asked about its colors.
+///
+/// Queries are written to the terminal as escape sequences, and the replies
+/// are read back from it. While a query is running, the terminal is put in
+/// raw mode, so anything the user types at the same time may be lost.
+/// Queries fail when the program is running as a background job, and are
+/// only supported on Unix.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use chalk_rs::Terminal;
+///
+/// let mut terminal = Terminal::open().unwrap();
+/// let background = terminal.background().unwrap();
+/// println!("the background is {}", background.to_hex());
+/// ```
+#[derive(Debug)]
+pub struct Terminal {
+	#[cfg_attr(not(unix), allow(dead_code))]
+	file: File,
+	timeout: Duration,
+}
+
+/// Puts a terminal into raw mode until it's dropped
+#[cfg(unix)]
+struct RawMode {
+	fd: libc::c_int,
+	original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+	fn new(fd: libc::c_int) -> io::Result<Self> {
+		// background jobs are stopped by SIGTTOU when they change the modes
+		// of their terminal. This fails for any other terminal, which
+		// doesn't send the signal.
+		let group = unsafe { libc::tcgetpgrp(fd) };
+		if group != -1 && group != unsafe { libc::getpgrp() } {
+			return Err(io::Error::other(
+				"the process is running in the background",
+			));
+		}
+
+		// SAFETY: termios is plain data, and is filled in by tcgetattr
+		let mut original = unsafe { std::mem::zeroed() };
+		if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		let mut raw = original;
+		raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+		raw.c_cc[libc::VMIN] = 0;
+		raw.c_cc[libc::VTIME] = 0;
+		if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(RawMode { fd, original })
+	}
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+	fn drop(&mut self) {
+		unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &self.original) };
+	}
+}
+
+/// Checks whether a reply ends with the reply to [`DEVICE_ATTRIBUTES`],
+/// which looks like `ESC [ ? 62 ; 22 c`
+#[cfg(unix)]
+fn has_device_attributes(reply: &[u8]) -> bool {
+	reply
+		.windows(3)
+		.position(|w| w == b"\x1b[?")
+		.is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// Gets the contents of every OSC sequence in a reply, such as
+/// `11;rgb:0000/0000/0000`. Sequences can end with either BEL or ST.
+pub(crate) fn osc_replies(reply: &str) -> impl Iterator<Item = &str> {
+	reply.split("\x1b]").skip(1).filter_map(|osc| {
+		let end = osc.find(['\x07', '\x1b'])?;
+		Some(&osc[..end])
+	})
+}
+
+/// Parses a color in the format used by X11 and in replies to OSC color
+/// queries, such as `rgb:ffff/8000/0000`. Each channel has one to four hex
+/// digits.
+pub(crate) fn parse_x11_color(color: &str) -> Option<RgbColor> {
+	let channels = color
+		.strip_prefix("rgb:")
+		.or_else(|| color.strip_prefix("rgba:"))?;
+
+	let mut rgb = [0; 3];
+	let mut channels = channels.split('/');
+	for channel in rgb.iter_mut() {
+		let hex = channels.next()?;
+		if hex.is_empty() || hex.len() > 4 {
+			return None;
+		}
+		let value = u32::from_str_radix(hex, 16).ok()?;
+		let max = (1 << (4 * hex.len())) - 1;
+		*channel = ((value * 255 + max / 2) / max) as u8;
+	}
+
+	Some(RgbColor::new(rgb[0], rgb[1], rgb[2]))
+}
+
+/// A color which the terminal can report
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Slot {
+	Index(u8),
+	Foreground,
+	Background,
+}
+
+/// Gets the colors in the reply to an OSC color query, such as
+/// `4;1;rgb:cdcd/0000/0000` or `11;rgb:0000/0000/0000`. Replies to OSC 4 may
+/// contain more than one color.
+fn reply_colors(reply: &str) -> Vec<(Slot, RgbColor)> {
+	let mut parts = reply.split(';');
+	let code = parts.next();
+	let parts: Vec<&str> = parts.collect();
+	match (code, parts.as_slice()) {
+		(Some("4"), pairs) => pairs
+			.chunks(2)
+			.filter_map(|pair| match pair {
+				[index, color] => Some((
+					Slot::Index(index.parse().ok()?),
+					parse_x11_color(color)?,
+				)),
+				_ => None,
+			})
+			.collect(),
+		(Some("10"), [color]) => parse_x11_color(color)
+			.map(|color| (Slot::Foreground, color))
+			.into_iter()
+			.collect(),
+		(Some("11"), [color]) => parse_x11_color(color)
+			.map(|color| (Slot::Background, color))
+			.into_iter()
+			.collect(),
+		_ => Vec::new(),
+	}
+}
+
+/// The error for a terminal which replied without the color it was asked for
+fn no_color() -> io::Error {
+	io::Error::new(
+		io::ErrorKind::Unsupported,
+		"the terminal didn't report its color",
+	)
+}
+
+impl Terminal {
+	/// Opens the terminal which controls this process, even if standard
+	/// input and output are redirected
+	#[cfg(unix)]
+	pub fn open() -> io::Result<Self> {
+		let file = std::fs::OpenOptions::new()
+			.read(true)
+			.write(true)
+			.open("/dev/tty")?;
+		Ok(Self::from_file(file))
+	}
+
+	/// Opens the terminal which controls this process. Terminals can only be
+	/// queried on Unix, so this always returns an error.
+	#[cfg(not(unix))]
+	pub fn open() -> io::Result<Self> {
+		Err(io::Error::new(
+			io::ErrorKind::Unsupported,
+			"terminals can only be queried on unix",
+		))
+	}
+
+	/// Uses an open terminal device, such as the secondary side of a
+	/// pseudo-terminal
+	pub fn from_file(file: File) -> Self {
+		Terminal {
+			file,
+			timeout: TIMEOUT,
+		}
+	}
+
+	/// Sets how long to wait for the terminal to reply. This is 200
+	/// milliseconds by default. Terminals which are connected over a slow
+	/// network may need longer.
+	#[inline(always)]
+	pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Writes a query to the terminal and reads back everything it replies
+	/// with, until the reply to [`DEVICE_ATTRIBUTES`] arrives
+	#[cfg(unix)]
+	pub(crate) fn query(&mut self, request: &str) -> io::Result<String> {
+		let fd = self.file.as_raw_fd();
+		let _raw = RawMode::new(fd)?;
+
+		write!(self.file, "{}{}", request, DEVICE_ATTRIBUTES)?;
+		self.file.flush()?;
+
+		let deadline = Instant::now() + self.timeout;
+		let mut reply = Vec::new();
+		while !has_device_attributes(&reply) {
+			let left = deadline.saturating_duration_since(Instant::now());
+			if left.is_zero() {
+				return Err(io::ErrorKind::TimedOut.into());
+			}
+
+			let mut poll = libc::pollfd {
+				fd,
+				events: libc::POLLIN,
+				revents: 0,
+			};
+			let millis = left.as_millis().max(1) as libc::c_int;
+			match unsafe { libc::poll(&mut poll, 1, millis) } {
+				0 => return Err(io::ErrorKind::TimedOut.into()),
+				n if n < 0 => {
+					let error = io::Error::last_os_error();
+					if error.kind() != io::ErrorKind::Interrupted {
+						return Err(error);
+					}
+				}
+				_ => {
+					let mut buffer = [0; 256];
+					let read = self.file.read(&mut buffer)?;
+					reply.extend_from_slice(&buffer[..read]);
+				}
+			}
+		}
+
+		Ok(String::from_utf8_lossy(&reply).into_owned())
+	}
+
+	#[cfg(not(unix))]
+	pub(crate) fn query(&mut self, _request: &str) -> io::Result<String> {
+		Err(io::ErrorKind::Unsupported.into())
+	}
+
+	/// Asks the terminal for a single color
+	fn query_color(
+		&mut self,
+		request: &str,
+		slot: Slot,
+	) -> io::Result<RgbColor> {
+		let reply = self.query(request)?;
+		let color = osc_replies(&reply)
+			.flat_map(reply_colors)
+			.find(|(s, _)| *s == slot);
+		color.map(|(_, color)| color).ok_or_else(no_color)
+	}
+
+	/// Asks the terminal for the color of text which doesn't have a
+	/// foreground color
+	pub fn foreground(&mut self) -> io::Result<RgbColor> {
+		self.query_color("\x1b]10;?\x1b\\", Slot::Foreground)
+	}
+
+	/// Asks the terminal for the color behind text which doesn't have a
+	/// background color
+	pub fn background(&mut self) -> io::Result<RgbColor> {
+		self.query_color("\x1b]11;?\x1b\\", Slot::Background)
+	}
+
+	/// Asks the terminal for the color it shows for an entry in its 256
+	/// color palette, using OSC 4
+	pub fn palette_color(&mut self, index: u8) -> io::Result<RgbColor> {
+		let request = format!("\x1b]4;{};?\x1b\\", index);
+		self.query_color(&request, Slot::Index(index))
+	}
+
+	/// Asks the terminal for every color in its palette, along with its
+	/// foreground and background colors.
+	///
+	/// Any color which the terminal doesn't report keeps its value from
+	/// [`Palette::xterm`]. Use [`Palette::detect`] to only ask once.
+	pub fn palette(&mut self) -> io::Result<Palette> {
+		let indices: String = (0..=255).map(|i| format!(";{};?", i)).collect();
+		let request = format!("\x1b]4{}\x1b\\{}", indices, DYNAMIC_COLORS);
+		let reply = self.query(&request)?;
+
+		let mut palette = Palette::xterm();
+		for (slot, color) in osc_replies(&reply).flat_map(reply_colors) {
+			match slot {
+				Slot::Index(index) => palette.set(index, color),
+				Slot::Foreground => palette.set_foreground(color),
+				Slot::Background => palette.set_background(color),
+			};
+		}
+
+		Ok(palette)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	/// Opens a pseudo-terminal, returning the side which acts as the
+	/// terminal, and the side which the program writes to
+	#[cfg(unix)]
+	fn pty() -> (File, File) {
+		use std::ffi::CStr;
+		use std::os::unix::io::FromRawFd;
+
+		unsafe {
+			let main = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+			assert!(main >= 0);
+			assert_eq!(libc::grantpt(main), 0);
+			assert_eq!(libc::unlockpt(main), 0);
+			let name = CStr::from_ptr(libc::ptsname(main));
+			let secondary = std::fs::OpenOptions::new()
+				.read(true)
+				.write(true)
+				.open(name.to_str().unwrap())
+				.unwrap();
+			(File::from_raw_fd(main), secondary)
+		}
+	}
+
+	/// Acts as a terminal which answers every query ending in `ESC [ c`
+	/// with the given reply
+	#[cfg(unix)]
+	fn fake_terminal(
+		reply: &'static str,
+	) -> (Terminal, std::thread::JoinHandle<String>) {
+		let (mut main, secondary) = pty();
+		let handle = std::thread::spawn(move || {
+			let mut query = Vec::new();
+			let mut buffer = [0; 256];
+			while !query.ends_with(b"\x1b[c") {
+				let read = main.read(&mut buffer).unwrap();
+				query.extend_from_slice(&buffer[..read]);
+			}
+			main.write_all(reply.as_bytes()).unwrap();
+			// keeps the terminal open until the reply has been read
+			std::thread::sleep(Duration::from_millis(100));
+			String::from_utf8(query).unwrap()
+		});
+
+		let mut terminal = Terminal::from_file(secondary);
+		terminal.timeout(Duration::from_secs(5));
+		(terminal, handle)
+	}
+
+	#[test]
+	fn parses_replies() {
+		let reply = "\x1b]11;rgb:ffff/8080/0000\x1b\\\x1b]10;rgb:f/0/80\x07";
+		let replies: Vec<&str> = osc_replies(reply).collect();
+		assert_eq!(replies, ["11;rgb:ffff/8080/0000", "10;rgb:f/0/80"]);
+
+		let color = parse_x11_color("rgb:ffff/8080/0000");
+		assert_eq!(color, Some(RgbColor::new(255, 128, 0)));
+		let color = parse_x11_color("rgb:f/0/80");
+		assert_eq!(color, Some(RgbColor::new(255, 0, 128)));
+		assert_eq!(parse_x11_color("rgb:ffff/8080"), None);
+		assert_eq!(parse_x11_color("#ffffff"), None);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn queries_background() {
+		let reply = "\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\\x1b[?62;22c";
+		let (mut terminal, handle) = fake_terminal(reply);
+		let background = terminal.background().unwrap();
+		assert_eq!(background, RgbColor::new(253, 246, 227));
+		assert_eq!(handle.join().unwrap(), "\x1b]11;?\x1b\\\x1b[c");
+
+		// terminals which don't know the query only reply to the other one
+		let (mut terminal, _) = fake_terminal("\x1b[?1;2c");
+		let error = terminal.foreground().unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn queries_palette() {
+		let reply = "\x1b]4;1;rgb:cdcd/3131/3131\x07\
+			\x1b]4;2;rgb:0d/bc/79;3;rgb:e5/e5/10\x1b\\\
+			\x1b]11;rgb:1e1e/1e1e/1e1e\x1b\\\x1b[?62;c";
+		let (mut terminal, handle) = fake_terminal(reply);
+		let palette = terminal.palette().unwrap();
+		assert_eq!(palette.get(1), RgbColor::new(205, 49, 49));
+		assert_eq!(palette.get(3), RgbColor::new(229, 229, 16));
+		assert_eq!(palette.background(), RgbColor::new(30, 30, 30));
+		// colors without a reply are the same as in xterm
+		assert_eq!(palette.get(4), Palette::xterm().get(4));
+		assert_eq!(palette.foreground(), Palette::xterm().foreground());
+
+		let query = handle.join().unwrap();
+		assert!(query.starts_with("\x1b]4;0;?;1;?;2;?"));
+		assert!(
+			query.ends_with(";255;?\x1b\\\x1b]10;?\x1b\\\x1b]11;?\x1b\\\x1b[c")
+		);
+
+		let (mut terminal, _) = fake_terminal(reply);
+		let color = terminal.palette_color(2).unwrap();
+		assert_eq!(color, RgbColor::new(13, 188, 121));
+	}
+}
@@ -1,5 +1,13 @@
-/*
-const BASIC_COLORS: [RgbColor; 16] = [
+use crate::ansi_chalk::AnsiColor;
+use crate::basic_chalk::BasicColor;
+use crate::color_names::{parse_rgb, ColorParseError, CSS_COLORS};
+
+use std::convert::TryFrom;
+use std::fmt::{LowerHex, UpperHex};
+use std::str::FromStr;
+
+/// The standard xterm values of the 16 basic colors, in [`BasicColor`] order
+pub(crate) const BASIC_COLORS: [RgbColor; 16] = [
 	RgbColor {
 		red: 0,
 		green: 0,
@@ -81,9 +89,8 @@ const BASIC_COLORS: [RgbColor; 16] = [
 		blue: 255,
 	},
 ];
-*/
 
-#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct RgbColor {
 	red: u8,
 	green: u8,
@@ -109,4 +116,158 @@ impl RgbColor {
 	pub const fn get_blue(&self) -> u8 {
 		self.blue
 	}
+
+	/// The squared euclidean distance between two colors
+	pub const fn distance(&self, other: &RgbColor) -> u32 {
+		let red = self.red.abs_diff(other.red) as u32;
+		let green = self.green.abs_diff(other.green) as u32;
+		let blue = self.blue.abs_diff(other.blue) as u32;
+		red * red + green * green + blue * blue
+	}
+
+	/// Finds the closest color in the xterm 256 color palette.
+	///
+	/// Only the color cube and the grayscale ramp are considered, since the
+	/// first 16 colors vary between terminals.
+	pub fn to_ansi(self) -> AnsiColor {
+		let red = nearest_cube_level(self.red);
+		let green = nearest_cube_level(self.green);
+		let blue = nearest_cube_level(self.blue);
+		let cube = AnsiColor::from_num(16 + 36 * red + 6 * green + blue);
+
+		let average =
+			(self.red as u16 + self.green as u16 + self.blue as u16) / 3;
+		let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+		let gray = AnsiColor::from_num(232 + gray_index);
+
+		if self.distance(&gray.to_rgb()) < self.distance(&cube.to_rgb()) {
+			gray
+		} else {
+			cube
+		}
+	}
+
+	/// Finds the closest of the 16 basic colors
+	pub fn to_basic(self) -> BasicColor {
+		let mut nearest = 0;
+		for (i, color) in BASIC_COLORS.iter().enumerate() {
+			if self.distance(color) < self.distance(&BASIC_COLORS[nearest]) {
+				nearest = i;
+			}
+		}
+
+		BasicColor::from_index(nearest as u8)
+	}
+
+	/// Formats the color as a hex code, such as `#ff8800`
+	pub fn to_hex(self) -> String {
+		format!("{:#x}", self)
+	}
+
+	/// Gets the CSS name of the color, if it has one
+	pub fn css_name(self) -> Option<&'static str> {
+		CSS_COLORS
+			.iter()
+			.find(|(_, color)| *color == self)
+			.map(|(name, _)| *name)
+	}
+
+	/// Finds the CSS named color which is closest to this one
+	pub fn nearest_css_name(self) -> &'static str {
+		let mut nearest = CSS_COLORS[0];
+		for color in CSS_COLORS {
+			if self.distance(&color.1) < self.distance(&nearest.1) {
+				nearest = color;
+			}
+		}
+
+		nearest.0
+	}
+}
+
+/// Parses a color in any of these forms:
+///
+/// * A hex code, such as `#ff8800` or `#f80`
+/// * An `rgb()` function, such as `rgb(255 136 0)` or `rgb(100%, 53%, 0%)`.
+///   An alpha may be given, as in `rgba(255, 136, 0, 0.5)`, but it's ignored.
+/// * A CSS color name, such as `rebeccapurple`
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::RgbColor;
+///
+/// let orange: RgbColor = "#f80".parse().unwrap();
+/// assert_eq!(orange, RgbColor::new(255, 136, 0));
+/// assert_eq!(orange.to_hex(), "#ff8800");
+/// assert_eq!(orange.nearest_css_name(), "darkorange");
+/// ```
+impl FromStr for RgbColor {
+	type Err = ColorParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		parse_rgb(s)
+	}
+}
+
+impl TryFrom<&str> for RgbColor {
+	type Error = ColorParseError;
+
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		parse_rgb(s)
+	}
+}
+
+/// Formats the color as six hex digits, with a `#` in front if the alternate
+/// flag is used
+impl LowerHex for RgbColor {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if f.alternate() {
+			f.write_str("#")?;
+		}
+		write!(f, "{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+	}
+}
+
+/// Formats the color as six hex digits, with a `#` in front if the alternate
+/// flag is used
+impl UpperHex for RgbColor {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if f.alternate() {
+			f.write_str("#")?;
+		}
+		write!(f, "{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+	}
+}
+
+/// The channel values used by the xterm color cube
+pub(crate) const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Finds the index of the cube level closest to the channel value
+const fn nearest_cube_level(value: u8) -> u8 {
+	if value < 48 {
+		0
+	} else if value < 115 {
+		1
+	} else {
+		(value - 35) / 40
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn palette_round_trip() {
+		for index in 16..=255 {
+			let color = AnsiColor::from_num(index);
+			assert_eq!(color.to_rgb().to_ansi().to_rgb(), color.to_rgb());
+		}
+
+		for (index, color) in BASIC_COLORS.iter().enumerate() {
+			assert_eq!(color.to_basic(), BasicColor::from_index(index as u8));
+		}
+	}
 }
@@ -0,0 +1,112 @@
+use crate::RgbColor;
+
+/// The longest sequence a chalk can write: every attribute, followed by three
+/// RGB colors, takes 78 bytes
+const CAPACITY: usize = 96;
+
+/// An SGR escape sequence which is built on the stack, joining all of its
+/// parameters into one sequence. It's empty if there aren't any parameters.
+#[derive(Clone, Copy)]
+pub(crate) struct Sgr {
+	bytes: [u8; CAPACITY],
+	len: usize,
+}
+
+impl Sgr {
+	#[inline(always)]
+	pub(crate) const fn new() -> Self {
+		Sgr {
+			bytes: [0; CAPACITY],
+			len: 0,
+		}
+	}
+
+	#[inline(always)]
+	fn push(&mut self, bytes: &[u8]) {
+		let end = self.len + bytes.len();
+		self.bytes[self.len..end].copy_from_slice(bytes);
+		self.len = end;
+	}
+
+	/// Starts the sequence, or separates the next parameter from the last
+	#[inline(always)]
+	fn separate(&mut self) {
+		if self.len == 0 {
+			self.push(b"\x1b[");
+		} else {
+			self.push(b";");
+		}
+	}
+
+	/// Adds a parameter, such as `1` or `4:3`
+	#[inline(always)]
+	pub(crate) fn param(&mut self, code: &str) {
+		self.separate();
+		self.push(code.as_bytes());
+	}
+
+	/// Adds a number as a parameter, without going through the formatting
+	/// machinery
+	#[inline(always)]
+	pub(crate) fn number(&mut self, n: u8) {
+		self.separate();
+		let digits = [b'0' + n / 100, b'0' + n / 10 % 10, b'0' + n % 10];
+		let start = match n {
+			100..=255 => 0,
+			10..=99 => 1,
+			_ => 2,
+		};
+		self.push(&digits[start..]);
+	}
+
+	/// Adds a color from the 256 color palette, after the parameter which
+	/// says what it's for, such as `38`
+	pub(crate) fn ansi(&mut self, code: &str, index: u8) {
+		self.param(code);
+		self.param("5");
+		self.number(index);
+	}
+
+	/// Adds a 24-bit color, after the parameter which says what it's for
+	pub(crate) fn rgb(&mut self, code: &str, color: RgbColor) {
+		self.param(code);
+		self.param("2");
+		self.number(color.get_red());
+		self.number(color.get_green());
+		self.number(color.get_blue());
+	}
+
+	/// Ends the sequence, if it was started
+	#[inline(always)]
+	pub(crate) fn finish(mut self) -> Self {
+		if self.len != 0 {
+			self.push(b"m");
+		}
+		self
+	}
+
+	#[inline(always)]
+	pub(crate) fn as_str(&self) -> &str {
+		// only whole strings and ascii digits are ever copied in
+		std::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn joins_params() {
+		let mut sgr = Sgr::new();
+		sgr.number(1);
+		sgr.param("4:3");
+		sgr.ansi("38", 208);
+		sgr.rgb("48", RgbColor::new(0, 42, 255));
+		let sgr = sgr.finish();
+		assert_eq!(sgr.as_str(), "\x1b[1;4:3;38;5;208;48;2;0;42;255m");
+
+		assert_eq!(Sgr::new().finish().as_str(), "");
+	}
+}
@@ -0,0 +1,374 @@
+use crate::color_names::parse_rgb;
+use crate::names::apply_name;
+use crate::{BasicColor, Chalk, ChalkType};
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+/// The names of the basic colors, in the order of their xterm indices
+const COLOR_NAMES: [&str; 16] = [
+	"black",
+	"red",
+	"green",
+	"yellow",
+	"blue",
+	"magenta",
+	"cyan",
+	"light_gray",
+	"gray",
+	"light_red",
+	"light_green",
+	"light_yellow",
+	"light_blue",
+	"light_magenta",
+	"light_cyan",
+	"white",
+];
+
+/// Other names for the basic colors
+const COLOR_ALIASES: [(&str, BasicColor); 3] = [
+	("light_grey", BasicColor::LightGray),
+	("grey", BasicColor::Gray),
+	("light_black", BasicColor::Gray),
+];
+
+type Setter = fn(&mut Chalk) -> &mut Chalk;
+type Check = fn(&Chalk) -> bool;
+
+/// The attributes, in the order they're written in
+const ATTRIBUTES: [(&str, Setter, Check); 18] = [
+	("bold", Chalk::bold, Chalk::is_bold),
+	("dim", Chalk::dim, Chalk::is_dim),
+	("italic", Chalk::italic, Chalk::is_italicized),
+	("underline", Chalk::underline, Chalk::is_single_underlined),
+	(
+		"double_underline",
+		Chalk::double_underline,
+		Chalk::is_double_underlined,
+	),
+	(
+		"curly_underline",
+		Chalk::curly_underline,
+		Chalk::is_curly_underlined,
+	),
+	(
+		"dotted_underline",
+		Chalk::dotted_underline,
+		Chalk::is_dotted_underlined,
+	),
+	(
+		"dashed_underline",
+		Chalk::dashed_underline,
+		Chalk::is_dashed_underlined,
+	),
+	("blink", Chalk::blink, Chalk::is_slow_blinking),
+	("rapid_blink", Chalk::rapid_blink, Chalk::is_rapid_blinking),
+	("invert", Chalk::invert, Chalk::is_inverted),
+	("hidden", Chalk::hide, Chalk::is_hidden),
+	(
+		"strikethrough",
+		Chalk::strikethrough,
+		Chalk::is_struck_through,
+	),
+	("overline", Chalk::overline, Chalk::is_overlined),
+	("framed", Chalk::frame, Chalk::is_framed),
+	("encircled", Chalk::encircle, Chalk::is_encircled),
+	("superscript", Chalk::superscript, Chalk::is_superscript),
+	("subscript", Chalk::subscript, Chalk::is_subscript),
+];
+
+/// Other names for attributes
+const ATTRIBUTE_ALIASES: [(&str, Setter); 5] = [
+	("reverse", Chalk::invert),
+	("inverse", Chalk::invert),
+	("hide", Chalk::hide),
+	("strike", Chalk::strikethrough),
+	("crossed_out", Chalk::strikethrough),
+];
+
+/// The reason a chalk couldn't be parsed
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ChalkParseErrorKind {
+	/// A word which isn't a color or an attribute
+	UnknownToken,
+	/// Something after `on` which isn't a color
+	NotAColor,
+	/// An `on` without a color after it
+	MissingBackground,
+	/// A color which has nowhere to go, such as a second foreground color
+	ExtraColor,
+}
+
+/// An error from parsing a [`Chalk`]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChalkParseError {
+	kind: ChalkParseErrorKind,
+	token: String,
+}
+
+impl ChalkParseError {
+	pub(crate) fn new(kind: ChalkParseErrorKind, token: &str) -> Self {
+		ChalkParseError {
+			kind,
+			token: token.to_string(),
+		}
+	}
+
+	/// What went wrong
+	pub const fn kind(&self) -> ChalkParseErrorKind {
+		self.kind
+	}
+
+	/// The word which couldn't be parsed
+	pub fn token(&self) -> &str {
+		&self.token
+	}
+}
+
+impl Display for ChalkParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.kind {
+			ChalkParseErrorKind::UnknownToken => {
+				write!(f, "unknown color or style `{}`", self.token)
+			}
+			ChalkParseErrorKind::NotAColor => {
+				write!(f, "`{}` isn't a background color", self.token)
+			}
+			ChalkParseErrorKind::MissingBackground => {
+				write!(f, "missing color after `{}`", self.token)
+			}
+			ChalkParseErrorKind::ExtraColor => {
+				write!(f, "too many colors at `{}`", self.token)
+			}
+		}
+	}
+}
+
+impl Error for ChalkParseError {}
+
+/// Splits a spec into words, keeping anything in parentheses together
+fn tokens(spec: &str) -> impl Iterator<Item = &str> {
+	let mut depth = 0_usize;
+	spec.split(move |c: char| {
+		match c {
+			'(' => depth += 1,
+			')' => depth = depth.saturating_sub(1),
+			_ => (),
+		}
+		depth == 0 && c.is_whitespace()
+	})
+	.filter(|token| !token.is_empty())
+}
+
+/// Parses a single color
+fn parse_color(token: &str) -> Option<ChalkType> {
+	let name = token.to_ascii_lowercase().replace('-', "_");
+	if name == "default" {
+		return Some(ChalkType::Default);
+	}
+
+	if let Some(i) = COLOR_NAMES.iter().position(|n| *n == name) {
+		return Some(ChalkType::Basic(BasicColor::from_index(i as u8)));
+	}
+	if let Some((_, color)) = COLOR_ALIASES.iter().find(|(n, _)| *n == name) {
+		return Some(ChalkType::Basic(*color));
+	}
+
+	let ansi = name.strip_prefix("ansi(").and_then(|n| n.strip_suffix(')'));
+	if let Some(index) = ansi {
+		return index.trim().parse().ok().map(ChalkType::ansi);
+	}
+
+	parse_rgb(token).ok().map(ChalkType::Rgb)
+}
+
+impl Chalk {
+	/// Writes the chalk in the form it's parsed from, such as
+	/// `bold italic red on #112233`
+	pub(crate) fn write_spec<W: Write + ?Sized>(
+		&self,
+		w: &mut W,
+	) -> std::fmt::Result {
+		let mut words = Vec::new();
+		for (name, _, is_set) in ATTRIBUTES.iter() {
+			if is_set(self) {
+				words.push(name.to_string());
+			}
+		}
+
+		let color = |color: &ChalkType| match color {
+			ChalkType::Default => "default".to_string(),
+			ChalkType::Basic(color) => {
+				COLOR_NAMES[color.index() as usize].to_string()
+			}
+			ChalkType::Ansi(color) => format!("ansi({})", color.as_num()),
+			ChalkType::Rgb(color) => color.to_hex(),
+		};
+
+		if self.underline_color != ChalkType::Default {
+			let underline = color(&self.underline_color);
+			words.push(format!("underline_color({})", underline));
+		}
+		if self.foreground != ChalkType::Default {
+			words.push(color(&self.foreground));
+		}
+		if self.background != ChalkType::Default {
+			if self.foreground == ChalkType::Default {
+				words.push("default".to_string());
+			}
+			words.push("on".to_string());
+			words.push(color(&self.background));
+		}
+		if words.is_empty() {
+			words.push("default".to_string());
+		}
+
+		w.write_str(&words.join(" "))
+	}
+
+	/// Gets the chalk in the form it's parsed from.
+	///
+	/// This is the same as formatting the chalk with `{:#}`.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::Chalk;
+	///
+	/// let chalk: Chalk = "italic on ansi(17) BOLD light_grey".parse().unwrap();
+	/// assert_eq!(chalk.spec(), "bold italic light_gray on ansi(17)");
+	/// assert_eq!(format!("{:#}", chalk), chalk.spec());
+	/// ```
+	pub fn spec(&self) -> String {
+		let mut spec = String::new();
+		// writing to a string can't fail
+		let _ = self.write_spec(&mut spec);
+		spec
+	}
+}
+
+/// Parses a chalk from a list of attributes and colors separated by spaces,
+/// such as `bold italic red on #112233`.
+///
+/// The attributes are `bold`, `dim`, `italic`, `underline`,
+/// `double_underline`, `curly_underline`, `dotted_underline`,
+/// `dashed_underline`, `blink`, `rapid_blink`, `invert`, `hidden`,
+/// `strikethrough`, `overline`, `framed`, `encircled`, `superscript`, and
+/// `subscript`. The color of the underline is given as
+/// `underline_color(color)`. Colors can be the name of a basic color, such as
+/// `light_red` or `grey`, `ansi(n)`, a hex code, `rgb(r, g, b)`, or a CSS
+/// color name. The color after `on` is the
+/// background color. Any chalk method which doesn't take arguments, such as
+/// `bg_blue`, is also accepted.
+impl FromStr for Chalk {
+	type Err = ChalkParseError;
+
+	fn from_str(spec: &str) -> Result<Self, Self::Err> {
+		let error = ChalkParseError::new;
+
+		let mut chalk = Chalk::default();
+		let mut tokens = tokens(spec);
+		while let Some(token) = tokens.next() {
+			let name = token.to_ascii_lowercase().replace('-', "_");
+			if name == "on" {
+				let kind = ChalkParseErrorKind::MissingBackground;
+				let color = tokens.next().ok_or_else(|| error(kind, token))?;
+				if chalk.background != ChalkType::Default {
+					return Err(error(ChalkParseErrorKind::ExtraColor, color));
+				}
+				let kind = ChalkParseErrorKind::NotAColor;
+				chalk.background =
+					parse_color(color).ok_or_else(|| error(kind, color))?;
+			} else if let Some((_, set, _)) =
+				ATTRIBUTES.iter().find(|(n, _, _)| *n == name)
+			{
+				set(&mut chalk);
+			} else if let Some((_, set)) =
+				ATTRIBUTE_ALIASES.iter().find(|(n, _)| *n == name)
+			{
+				set(&mut chalk);
+			} else if let Some(color) = parse_color(token) {
+				if chalk.foreground != ChalkType::Default {
+					return Err(error(ChalkParseErrorKind::ExtraColor, token));
+				}
+				chalk.foreground = color;
+			} else if let Some(color) = name
+				.strip_prefix("underline_color(")
+				.and_then(|c| c.strip_suffix(')'))
+			{
+				if chalk.underline_color != ChalkType::Default {
+					return Err(error(ChalkParseErrorKind::ExtraColor, token));
+				}
+				let kind = ChalkParseErrorKind::NotAColor;
+				chalk.underline_color =
+					parse_color(color).ok_or_else(|| error(kind, color))?;
+			} else if !apply_name(&mut chalk, &name) {
+				return Err(error(ChalkParseErrorKind::UnknownToken, token));
+			}
+		}
+
+		Ok(chalk)
+	}
+}
+
+impl TryFrom<&str> for Chalk {
+	type Error = ChalkParseError;
+
+	fn try_from(spec: &str) -> Result<Self, Self::Error> {
+		spec.parse()
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		let chalk: Chalk = "bold italic red on #112233".parse().unwrap();
+		let mut expected = Chalk::new();
+		expected.bold().italic().red().bg_rgb(0x11, 0x22, 0x33);
+		assert_eq!(chalk, expected);
+		assert_eq!(chalk.spec(), "bold italic red on #112233");
+
+		let specs = [
+			"default",
+			"default on light_cyan",
+			"dim double_underline blink invert hidden ansi(200)",
+			"curly_underline rapid_blink strikethrough overline framed \
+			superscript underline_color(#ff0000) blue",
+			"underline #abcdef on white",
+		];
+		for spec in specs.iter() {
+			assert_eq!(spec.parse::<Chalk>().unwrap().spec(), *spec);
+		}
+
+		let parsed: Chalk =
+			"Light-Black on rgb(1, 2, 3) bg_blue".parse().unwrap();
+		assert_eq!(parsed, Chalk::new().with_gray().with_bg_blue());
+	}
+
+	#[test]
+	fn errors() {
+		let error = "bold redd".parse::<Chalk>().unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::UnknownToken);
+		assert_eq!(error.token(), "redd");
+		let error = "red on bold".parse::<Chalk>().unwrap_err();
+		assert_eq!(error.to_string(), "`bold` isn't a background color");
+		let error = "red on".parse::<Chalk>().unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::MissingBackground);
+		let error = "red bold blue".parse::<Chalk>().unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::ExtraColor);
+		assert_eq!(error.token(), "blue");
+		let error = "red on blue on green".parse::<Chalk>().unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::ExtraColor);
+		assert_eq!(error.token(), "green");
+		let spec = "underline_color(red) underline_color(#00f)";
+		let error = spec.parse::<Chalk>().unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::ExtraColor);
+		assert_eq!(error.token(), "underline_color(#00f)");
+	}
+}
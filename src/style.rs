@@ -1,195 +1,520 @@
-use crate::{
-	enum_default, enum_display, enum_fmt_impl, enum_impls, impl_enums,
-};
-
-use std::fmt::Binary;
-use std::fmt::Display;
-use std::fmt::LowerHex;
-use std::fmt::Octal;
-use std::fmt::UpperHex;
-
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum Weight {
-	Default,
-	Bold = 1,
-	Dim = 2,
+use crate::sgr::Sgr;
+
+/// A group of bits in a [`StyleMap`] which holds one attribute. The value is
+/// zero when the attribute isn't set.
+#[derive(Clone, Copy)]
+struct Field {
+	shift: u16,
+	mask: u16,
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-enum Underline {
-	Default,
-	Single = 4,
-	Double = 21,
+impl Field {
+	const fn new(shift: u16, bits: u16) -> Self {
+		Field {
+			shift,
+			mask: (1 << bits) - 1,
+		}
+	}
 }
 
-#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct StyleMap {
-	weight: Weight,
-	underline: Underline,
-	italic: bool,
-	blink: bool,
-	invert: bool,
-	hidden: bool
-}
+const WEIGHT: Field = Field::new(0, 2);
+const UNDERLINE: Field = Field::new(2, 3);
+const ITALIC: Field = Field::new(5, 1);
+const BLINK: Field = Field::new(6, 2);
+const INVERT: Field = Field::new(8, 1);
+const HIDDEN: Field = Field::new(9, 1);
+const STRIKETHROUGH: Field = Field::new(10, 1);
+const OVERLINE: Field = Field::new(11, 1);
+const FRAME: Field = Field::new(12, 2);
+const SCRIPT: Field = Field::new(14, 2);
+
+const SET: u16 = 1;
+
+const BOLD: u16 = 1;
+const DIM: u16 = 2;
+
+const SINGLE: u16 = 1;
+const DOUBLE: u16 = 2;
+const CURLY: u16 = 3;
+const DOTTED: u16 = 4;
+const DASHED: u16 = 5;
+
+const SLOW: u16 = 1;
+const RAPID: u16 = 2;
+
+const FRAMED: u16 = 1;
+const ENCIRCLED: u16 = 2;
+
+const SUPERSCRIPT: u16 = 1;
+const SUBSCRIPT: u16 = 2;
+
+/// Each field in the order they're written, with the SGR parameters which
+/// turn on each of its values and the one which turns it off
+static CODES: [(Field, &[&str], &str); 10] = [
+	(WEIGHT, &["1", "2"], "22"),
+	(UNDERLINE, &["4", "21", "4:3", "4:4", "4:5"], "24"),
+	(ITALIC, &["3"], "23"),
+	(BLINK, &["5", "6"], "25"),
+	(INVERT, &["7"], "27"),
+	(HIDDEN, &["8"], "28"),
+	(STRIKETHROUGH, &["9"], "29"),
+	(FRAME, &["51", "52"], "54"),
+	(OVERLINE, &["53"], "55"),
+	(SCRIPT, &["73", "74"], "75"),
+];
+
+/// The attributes of a chalk, packed into two bytes
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct StyleMap(u16);
 
-impl_enums!(Weight, Underline);
+impl StyleMap {
+	/// Creates a map with no attributes set
+	#[inline(always)]
+	pub(crate) const fn new() -> Self {
+		StyleMap(0)
+	}
 
-impl Display for StyleMap {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let mut ansi_str = String::with_capacity(3);
+	#[inline(always)]
+	const fn get(&self, field: Field) -> u16 {
+		(self.0 >> field.shift) & field.mask
+	}
 
-		// initially set it to the weight if there is one
-		if self.weight != Weight::Default {
-			ansi_str += format!("\x1b[{}m", self.weight).as_str();
-		}
+	#[inline(always)]
+	const fn set(&mut self, field: Field, value: u16) -> &mut Self {
+		self.0 =
+			(self.0 & !(field.mask << field.shift)) | (value << field.shift);
+		self
+	}
 
-		if self.underline != Underline::Default {
-			ansi_str += format!("\x1b[{}m", self.underline).as_str();
+	/// Adds the parameters which turn on each attribute that is set
+	pub(crate) fn write_open(&self, sgr: &mut Sgr) {
+		for &(field, codes, _) in CODES.iter() {
+			let value = self.get(field);
+			if value != 0 {
+				sgr.param(codes[value as usize - 1]);
+			}
 		}
+	}
 
-		if self.italic {
-			ansi_str += "\x1b[3m";
-		}
-		if self.blink {
-			ansi_str += "\x1b[6m"
+	/// Adds the parameters which turn off each attribute that is set,
+	/// leaving every other attribute alone
+	pub(crate) fn write_close(&self, sgr: &mut Sgr) {
+		for &(field, _, close) in CODES.iter() {
+			if self.get(field) != 0 {
+				sgr.param(close);
+			}
 		}
-		if self.invert {
-			ansi_str += "\x1b[7m";
+	}
+
+	/// Sets each attribute which is set in the other map, leaving the rest
+	pub(crate) fn layer(&mut self, top: &StyleMap) {
+		for &(field, _, _) in CODES.iter() {
+			let value = top.get(field);
+			if value != 0 {
+				self.set(field, value);
+			}
 		}
-		if self.hidden {
-			ansi_str += "\x1b[8m";
+	}
+
+	/// Checks whether every attribute which is set in the other map is set
+	/// the same way in this one
+	pub(crate) fn contains(&self, other: &StyleMap) -> bool {
+		CODES.iter().all(|&(field, _, _)| {
+			other.get(field) == 0 || other.get(field) == self.get(field)
+		})
+	}
+
+	/// Gets the attributes which are set in this map but not in the other one
+	pub(crate) fn without(&self, other: &StyleMap) -> StyleMap {
+		let mut map = *self;
+		for &(field, _, _) in CODES.iter() {
+			if self.get(field) == other.get(field) {
+				map.set(field, 0);
+			}
 		}
 
-		write!(f, "{}", ansi_str)
+		map
 	}
-}
 
-impl StyleMap {
-	pub fn reset_style(&mut self) -> &mut Self {
-		self.reset_weight();
-		self.stop_blink();
-		self.no_underline();
-		self.unitalic();
-		self.uninvert();
-		self.unhide();
+	pub const fn reset_style(&mut self) -> &mut Self {
+		self.0 = 0;
 		self
 	}
 
-	pub fn reset_weight(&mut self) -> &mut Self {
-		self.weight = Weight::Default;
-		self
+	pub const fn reset_weight(&mut self) -> &mut Self {
+		self.set(WEIGHT, 0)
 	}
 
-	pub fn bold(&mut self) -> &mut Self {
-		self.weight = Weight::Bold;
-		self
+	pub const fn bold(&mut self) -> &mut Self {
+		self.set(WEIGHT, BOLD)
 	}
 
-	pub fn dim(&mut self) -> &mut Self {
-		self.weight = Weight::Dim;
-		self
+	pub const fn dim(&mut self) -> &mut Self {
+		self.set(WEIGHT, DIM)
 	}
 
-	pub fn is_normal_weight(&self) -> bool {
-		self.weight == Weight::Default
+	pub const fn is_normal_weight(&self) -> bool {
+		self.get(WEIGHT) == 0
 	}
 
-	pub fn is_bold(&self) -> bool {
-		self.weight == Weight::Bold
+	pub const fn is_bold(&self) -> bool {
+		self.get(WEIGHT) == BOLD
 	}
 
-	pub fn is_dim(&self) -> bool {
-		self.weight == Weight::Dim
+	pub const fn is_dim(&self) -> bool {
+		self.get(WEIGHT) == DIM
 	}
 
-	pub fn italic(&mut self) -> &mut Self {
-		self.italic = true;
-		self
+	pub const fn italic(&mut self) -> &mut Self {
+		self.set(ITALIC, SET)
 	}
 
-	pub fn unitalic(&mut self) -> &mut Self {
-		self.italic = false;
-		self
+	pub const fn unitalic(&mut self) -> &mut Self {
+		self.set(ITALIC, 0)
 	}
 
 	pub const fn is_italicized(&self) -> bool {
-		self.italic
+		self.get(ITALIC) == SET
 	}
 
-	pub fn no_underline(&mut self) -> &mut Self {
-		self.underline = Underline::Default;
-		self
+	pub const fn no_underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, 0)
 	}
 
-	pub fn underline(&mut self) -> &mut Self {
-		self.underline = Underline::Single;
-		self
+	pub const fn underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, SINGLE)
 	}
 
-	pub fn double_underline(&mut self) -> &mut Self {
-		self.underline = Underline::Double;
-		self
+	pub const fn double_underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, DOUBLE)
 	}
 
-	pub fn num_underlines(&self) -> u8 {
-		match self.underline {
-			Underline::Default => 0,
-			Underline::Single => 1,
-			Underline::Double => 2,
+	pub const fn curly_underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, CURLY)
+	}
+
+	pub const fn dotted_underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, DOTTED)
+	}
+
+	pub const fn dashed_underline(&mut self) -> &mut Self {
+		self.set(UNDERLINE, DASHED)
+	}
+
+	pub const fn num_underlines(&self) -> u8 {
+		match self.get(UNDERLINE) {
+			0 => 0,
+			DOUBLE => 2,
+			_ => 1,
 		}
 	}
 
-	pub fn has_underlines(&self) -> bool {
-		self.underline != Underline::Default
+	pub const fn has_underlines(&self) -> bool {
+		self.get(UNDERLINE) != 0
 	}
 
-	pub fn is_single_underlined(&self) -> bool {
-		self.underline == Underline::Single
+	pub const fn is_single_underlined(&self) -> bool {
+		self.get(UNDERLINE) == SINGLE
 	}
 
-	pub fn is_double_underlined(&self) -> bool {
-		self.underline == Underline::Double
+	pub const fn is_double_underlined(&self) -> bool {
+		self.get(UNDERLINE) == DOUBLE
 	}
 
-	pub fn stop_blink(&mut self) -> &mut Self {
-		self.blink = false;
-		self
+	pub const fn is_curly_underlined(&self) -> bool {
+		self.get(UNDERLINE) == CURLY
 	}
 
-	pub fn blink(&mut self) -> &mut Self {
-		self.blink = true;
-		self
+	pub const fn is_dotted_underlined(&self) -> bool {
+		self.get(UNDERLINE) == DOTTED
+	}
+
+	pub const fn is_dashed_underlined(&self) -> bool {
+		self.get(UNDERLINE) == DASHED
+	}
+
+	pub const fn stop_blink(&mut self) -> &mut Self {
+		self.set(BLINK, 0)
+	}
+
+	/// Blinks slowly, less than 150 times per minute
+	pub const fn blink(&mut self) -> &mut Self {
+		self.set(BLINK, SLOW)
+	}
+
+	/// Blinks quickly, 150 times per minute or more. Few terminals support
+	/// this.
+	pub const fn rapid_blink(&mut self) -> &mut Self {
+		self.set(BLINK, RAPID)
 	}
 
 	pub const fn is_blinking(&self) -> bool {
-		self.blink
+		self.get(BLINK) != 0
 	}
 
-	pub fn invert(&mut self) -> &mut Self {
-		self.invert = true;
-		self
+	pub const fn is_slow_blinking(&self) -> bool {
+		self.get(BLINK) == SLOW
 	}
 
-	pub fn uninvert(&mut self) -> &mut Self {
-		self.invert = false;
-		self
+	pub const fn is_rapid_blinking(&self) -> bool {
+		self.get(BLINK) == RAPID
+	}
+
+	pub const fn invert(&mut self) -> &mut Self {
+		self.set(INVERT, SET)
+	}
+
+	pub const fn uninvert(&mut self) -> &mut Self {
+		self.set(INVERT, 0)
 	}
 
 	pub const fn is_inverted(&self) -> bool {
-		self.invert
+		self.get(INVERT) == SET
 	}
 
-	pub fn hide(&mut self) -> &mut Self {
-		self.hidden = true;
-		self
+	pub const fn hide(&mut self) -> &mut Self {
+		self.set(HIDDEN, SET)
 	}
 
-	pub fn unhide(&mut self) -> &mut Self {
-		self.hidden = false;
-		self
+	pub const fn unhide(&mut self) -> &mut Self {
+		self.set(HIDDEN, 0)
 	}
 
 	pub const fn is_hidden(&self) -> bool {
-		self.hidden
+		self.get(HIDDEN) == SET
+	}
+
+	pub const fn strikethrough(&mut self) -> &mut Self {
+		self.set(STRIKETHROUGH, SET)
+	}
+
+	pub const fn no_strikethrough(&mut self) -> &mut Self {
+		self.set(STRIKETHROUGH, 0)
+	}
+
+	pub const fn is_struck_through(&self) -> bool {
+		self.get(STRIKETHROUGH) == SET
+	}
+
+	pub const fn overline(&mut self) -> &mut Self {
+		self.set(OVERLINE, SET)
+	}
+
+	pub const fn no_overline(&mut self) -> &mut Self {
+		self.set(OVERLINE, 0)
+	}
+
+	pub const fn is_overlined(&self) -> bool {
+		self.get(OVERLINE) == SET
+	}
+
+	pub const fn frame(&mut self) -> &mut Self {
+		self.set(FRAME, FRAMED)
+	}
+
+	pub const fn encircle(&mut self) -> &mut Self {
+		self.set(FRAME, ENCIRCLED)
+	}
+
+	pub const fn no_frame(&mut self) -> &mut Self {
+		self.set(FRAME, 0)
+	}
+
+	pub const fn is_framed(&self) -> bool {
+		self.get(FRAME) == FRAMED
+	}
+
+	pub const fn is_encircled(&self) -> bool {
+		self.get(FRAME) == ENCIRCLED
+	}
+
+	pub const fn superscript(&mut self) -> &mut Self {
+		self.set(SCRIPT, SUPERSCRIPT)
+	}
+
+	pub const fn subscript(&mut self) -> &mut Self {
+		self.set(SCRIPT, SUBSCRIPT)
+	}
+
+	pub const fn no_script(&mut self) -> &mut Self {
+		self.set(SCRIPT, 0)
+	}
+
+	pub const fn is_superscript(&self) -> bool {
+		self.get(SCRIPT) == SUPERSCRIPT
+	}
+
+	pub const fn is_subscript(&self) -> bool {
+		self.get(SCRIPT) == SUBSCRIPT
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	fn open(map: &StyleMap) -> String {
+		let mut sgr = Sgr::new();
+		map.write_open(&mut sgr);
+		sgr.finish().as_str().to_string()
+	}
+
+	fn close(map: &StyleMap) -> String {
+		let mut sgr = Sgr::new();
+		map.write_close(&mut sgr);
+		sgr.finish().as_str().to_string()
+	}
+
+	#[test]
+	fn fields_are_packed() {
+		let mut bits = 0;
+		for &(field, codes, _) in CODES.iter() {
+			let mask = field.mask << field.shift;
+			assert_eq!(bits & mask, 0, "fields overlap");
+			assert!(codes.len() as u16 <= field.mask);
+			bits |= mask;
+		}
+		assert_eq!(bits, u16::MAX);
+
+		let mut map = StyleMap::new();
+		map.dim()
+			.dashed_underline()
+			.italic()
+			.rapid_blink()
+			.invert()
+			.hide()
+			.strikethrough()
+			.overline()
+			.encircle()
+			.subscript();
+		map.bold().underline();
+		assert!(map.is_bold());
+		assert!(map.is_single_underlined());
+		assert!(map.is_italicized());
+		assert!(map.is_rapid_blinking());
+		assert!(map.is_inverted());
+		assert!(map.is_hidden());
+		assert!(map.is_struck_through());
+		assert!(map.is_overlined());
+		assert!(map.is_encircled());
+		assert!(map.is_subscript());
+
+		map.reset_weight().no_underline();
+		assert!(map.is_normal_weight());
+		assert!(!map.has_underlines());
+		assert!(map.is_italicized());
+		assert!(map.is_subscript());
+	}
+
+	#[test]
+	fn codes() {
+		assert_eq!(open(&StyleMap::new()), "");
+		assert_eq!(close(&StyleMap::new()), "");
+
+		let mut map = StyleMap::new();
+		map.dim().curly_underline().blink().frame().superscript();
+		assert_eq!(open(&map), "\x1b[2;4:3;5;51;73m");
+		assert_eq!(close(&map), "\x1b[22;24;25;54;75m");
+
+		let mut map = StyleMap::new();
+		map.double_underline().italic().hide().overline();
+		assert_eq!(open(&map), "\x1b[21;3;8;53m");
+		assert_eq!(close(&map), "\x1b[24;23;28;55m");
+	}
+
+	#[test]
+	fn contains() {
+		let mut map = StyleMap::new();
+		map.bold().underline().italic();
+		let mut other = StyleMap::new();
+		assert!(map.contains(&other));
+		other.bold().italic();
+		assert!(map.contains(&other));
+		other.double_underline();
+		assert!(!map.contains(&other));
+		other.underline();
+		assert!(map.contains(&other));
+		other.dim();
+		assert!(!map.contains(&other));
+	}
+
+	#[test]
+	fn without() {
+		let mut map = StyleMap::new();
+		map.bold().underline().italic();
+		let mut other = StyleMap::new();
+		other.bold().double_underline().invert();
+
+		let mut expected = StyleMap::new();
+		expected.underline().italic();
+		assert_eq!(map.without(&other), expected);
+		assert_eq!(map.without(&map), StyleMap::new());
+		assert_eq!(map.without(&StyleMap::new()), map);
+	}
+
+	#[test]
+	fn layer() {
+		let mut map = StyleMap::new();
+		map.bold().underline();
+		let mut top = StyleMap::new();
+		top.dim().italic();
+		map.layer(&top);
+
+		let mut expected = StyleMap::new();
+		expected.dim().underline().italic();
+		assert_eq!(map, expected);
+	}
+
+	#[test]
+	fn set_and_clear() {
+		let mut map = StyleMap::default();
+		map.strikethrough().overline().encircle().superscript();
+		map.curly_underline();
+		assert!(map.is_struck_through());
+		assert!(map.is_overlined());
+		assert!(map.is_encircled() && !map.is_framed());
+		assert!(map.is_superscript() && !map.is_subscript());
+		assert!(map.is_curly_underlined() && map.has_underlines());
+		assert_eq!(map.num_underlines(), 1);
+
+		map.frame().subscript().dotted_underline();
+		assert!(map.is_framed() && !map.is_encircled());
+		assert!(map.is_subscript() && !map.is_superscript());
+		assert!(map.is_dotted_underlined() && !map.is_curly_underlined());
+		map.dashed_underline();
+		assert!(map.is_dashed_underlined());
+
+		map.no_strikethrough().no_overline().no_frame().no_script();
+		assert!(!map.is_struck_through());
+		assert!(!map.is_overlined());
+		assert!(!map.is_framed() && !map.is_encircled());
+		assert!(!map.is_superscript() && !map.is_subscript());
+		assert!(map.is_dashed_underlined());
+		map.no_underline();
+		assert_eq!(map, StyleMap::default());
+	}
+
+	#[test]
+	fn combine() {
+		let mut map = StyleMap::default();
+		map.bold().strikethrough().frame();
+		let mut top = StyleMap::default();
+		top.overline().encircle().subscript().dotted_underline();
+		map.layer(&top);
+		assert!(map.is_bold() && map.is_struck_through());
+		assert!(map.is_overlined() && map.is_encircled());
+		assert!(map.is_subscript() && map.is_dotted_underlined());
+
+		assert!(map.contains(&top));
+		assert!(!top.contains(&map));
+		let mut framed = StyleMap::default();
+		framed.frame();
+		assert!(!map.contains(&framed));
+
+		let mut expected = StyleMap::default();
+		expected.bold().strikethrough();
+		assert_eq!(map.without(&top), expected);
+		assert_eq!(map.without(&map), StyleMap::default());
 	}
 }
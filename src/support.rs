@@ -0,0 +1,440 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(windows)]
+use winapi::{
+	shared::minwindef::DWORD,
+	um::consoleapi::GetConsoleMode,
+	um::consoleapi::SetConsoleMode,
+	um::processenv::GetStdHandle,
+	um::winbase::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE},
+	um::wincon::ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+};
+
+/// The amount of color a terminal is able to display
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ColorLevel {
+	/// No escape sequences should be written at all
+	None,
+	/// The 16 basic colors
+	Basic,
+	/// The 256 color xterm palette
+	Ansi256,
+	/// 24-bit RGB colors
+	TrueColor,
+}
+
+/// An output stream which may or may not be connected to a terminal
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Stream {
+	#[default]
+	Stdout,
+	Stderr,
+}
+
+const UNKNOWN: u8 = u8::MAX;
+
+static DETECTED: [AtomicU8; 2] =
+	[AtomicU8::new(UNKNOWN), AtomicU8::new(UNKNOWN)];
+static OVERRIDE: [AtomicU8; 2] =
+	[AtomicU8::new(UNKNOWN), AtomicU8::new(UNKNOWN)];
+static HYPERLINKS: [AtomicU8; 2] =
+	[AtomicU8::new(UNKNOWN), AtomicU8::new(UNKNOWN)];
+static HYPERLINKS_OVERRIDE: [AtomicU8; 2] =
+	[AtomicU8::new(UNKNOWN), AtomicU8::new(UNKNOWN)];
+static TERMINAL_HYPERLINKS: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// CI services whose log viewers are known to understand escape sequences
+const CI_TRUECOLOR: [&str; 2] = ["GITHUB_ACTIONS", "GITEA_ACTIONS"];
+const CI_BASIC: [&str; 6] = [
+	"TRAVIS",
+	"CIRCLECI",
+	"APPVEYOR",
+	"GITLAB_CI",
+	"BUILDKITE",
+	"DRONE",
+];
+
+/// Values of `TERM_PROGRAM` for terminals which show hyperlinks
+const LINK_PROGRAMS: [&str; 6] = [
+	"iTerm.app",
+	"WezTerm",
+	"vscode",
+	"Hyper",
+	"ghostty",
+	"terminology",
+];
+
+/// Values of `TERM` for terminals which show hyperlinks
+const LINK_TERMS: [&str; 4] = ["xterm-kitty", "alacritty", "foot", "contour"];
+
+/// Variables which are only set by terminals that show hyperlinks
+const LINK_VARS: [&str; 4] = [
+	"WT_SESSION",
+	"KONSOLE_VERSION",
+	"DOMTERM",
+	"KITTY_WINDOW_ID",
+];
+
+/// Terminals which understand at least the basic colors
+const COLOR_TERMS: [&str; 10] = [
+	"xterm", "screen", "vt100", "vt220", "rxvt", "color", "ansi", "cygwin",
+	"linux", "tmux",
+];
+
+impl Stream {
+	#[inline(always)]
+	const fn index(self) -> usize {
+		self as usize
+	}
+
+	/// Checks whether the stream is connected to a terminal
+	pub fn is_terminal(self) -> bool {
+		match self {
+			Stream::Stdout => io::stdout().is_terminal(),
+			Stream::Stderr => io::stderr().is_terminal(),
+		}
+	}
+
+	/// Checks whether the terminal the stream is connected to shows OSC 8
+	/// hyperlinks.
+	///
+	/// The environment is only inspected the first time this is called for
+	/// each stream. Terminals which aren't known to show links are assumed
+	/// not to, since the others may print the escape sequences as text.
+	pub fn supports_hyperlinks(self) -> bool {
+		let forced = HYPERLINKS_OVERRIDE[self.index()].load(Ordering::Relaxed);
+		if forced != UNKNOWN {
+			return forced == 1;
+		}
+
+		let cached = HYPERLINKS[self.index()].load(Ordering::Relaxed);
+		if cached != UNKNOWN {
+			return cached == 1;
+		}
+
+		let supported = Self::hyperlinks_from_env(self.is_terminal(), |key| {
+			env::var(key).ok()
+		});
+		HYPERLINKS[self.index()].store(supported as u8, Ordering::Relaxed);
+		supported
+	}
+
+	/// Forces whether hyperlinks are written to a stream, or goes back to
+	/// automatic detection if `None` is given
+	pub fn set_hyperlinks_override(self, enabled: Option<bool>) {
+		let enabled = enabled.map_or(UNKNOWN, |enabled| enabled as u8);
+		HYPERLINKS_OVERRIDE[self.index()].store(enabled, Ordering::Relaxed);
+	}
+
+	/// Determines whether a terminal shows hyperlinks from a set of
+	/// environment variables. `FORCE_HYPERLINK` turns them on, unless it's
+	/// set to `0`.
+	///
+	/// # Arguments
+	///
+	/// * `is_terminal` - Whether the stream is connected to a terminal
+	/// * `var` - Looks up an environment variable
+	pub fn hyperlinks_from_env(
+		is_terminal: bool,
+		var: impl Fn(&str) -> Option<String>,
+	) -> bool {
+		if let Some(force) = var("FORCE_HYPERLINK") {
+			return force.trim() != "0";
+		}
+
+		if !is_terminal {
+			return false;
+		}
+
+		let program = var("TERM_PROGRAM").unwrap_or_default();
+		let term = var("TERM").unwrap_or_default();
+		let vte = var("VTE_VERSION").and_then(|v| v.parse::<u32>().ok());
+
+		LINK_PROGRAMS.contains(&program.as_str())
+			|| LINK_TERMS.iter().any(|name| term.starts_with(name))
+			|| LINK_VARS.iter().any(|name| var(name).is_some())
+			|| vte.is_some_and(|version| version >= 5000)
+	}
+}
+
+impl ColorLevel {
+	const fn from_u8(level: u8) -> Option<Self> {
+		match level {
+			0 => Some(ColorLevel::None),
+			1 => Some(ColorLevel::Basic),
+			2 => Some(ColorLevel::Ansi256),
+			3 => Some(ColorLevel::TrueColor),
+			_ => None,
+		}
+	}
+
+	/// Gets the color level of the given stream.
+	///
+	/// The environment is only inspected the first time this is called for
+	/// each stream. An override set with [`ColorLevel::set_override`] takes
+	/// precedence over the detected level.
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{ColorLevel, Stream};
+	///
+	/// if ColorLevel::detect(Stream::Stdout) >= ColorLevel::Ansi256 {
+	/// 	println!("256 colors are available");
+	/// }
+	/// ```
+	pub fn detect(stream: Stream) -> Self {
+		let forced = OVERRIDE[stream.index()].load(Ordering::Relaxed);
+		if let Some(level) = Self::from_u8(forced) {
+			return level;
+		}
+
+		let cached = DETECTED[stream.index()].load(Ordering::Relaxed);
+		if let Some(level) = Self::from_u8(cached) {
+			return level;
+		}
+
+		let level =
+			Self::from_env(stream.is_terminal(), |key| env::var(key).ok());
+		DETECTED[stream.index()].store(level as u8, Ordering::Relaxed);
+		level
+	}
+
+	/// The color level of standard output
+	#[inline(always)]
+	pub fn stdout() -> Self {
+		Self::detect(Stream::Stdout)
+	}
+
+	/// The color level of standard error
+	#[inline(always)]
+	pub fn stderr() -> Self {
+		Self::detect(Stream::Stderr)
+	}
+
+	/// Forces the color level used for a stream, or goes back to automatic
+	/// detection if `None` is given.
+	///
+	/// This is useful for implementing a `--color=always` flag.
+	pub fn set_override(stream: Stream, level: Option<ColorLevel>) {
+		let level = level.map_or(UNKNOWN, |level| level as u8);
+		OVERRIDE[stream.index()].store(level, Ordering::Relaxed);
+	}
+
+	/// Determines the color level from a set of environment variables.
+	///
+	/// # Arguments
+	///
+	/// * `is_terminal` - Whether the stream is connected to a terminal
+	/// * `var` - Looks up an environment variable
+	pub fn from_env(
+		is_terminal: bool,
+		var: impl Fn(&str) -> Option<String>,
+	) -> Self {
+		if let Some(force) = var("FORCE_COLOR") {
+			match force.as_str() {
+				"0" | "false" => return ColorLevel::None,
+				"2" => return ColorLevel::Ansi256,
+				"3" => return ColorLevel::TrueColor,
+				_ => {
+					let level = Self::from_term(&var);
+					return level.max(ColorLevel::Basic);
+				}
+			}
+		}
+
+		if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+			return ColorLevel::None;
+		}
+
+		let clicolor_force =
+			var("CLICOLOR_FORCE").is_some_and(|value| value != "0");
+		if clicolor_force {
+			return Self::from_term(&var).max(ColorLevel::Basic);
+		}
+
+		if !is_terminal || var("CLICOLOR").as_deref() == Some("0") {
+			return ColorLevel::None;
+		}
+
+		Self::from_term(&var)
+	}
+
+	/// Determines the color level of a terminal from its environment
+	fn from_term(var: &impl Fn(&str) -> Option<String>) -> Self {
+		let term = var("TERM").unwrap_or_default();
+		if term == "dumb" {
+			return ColorLevel::None;
+		}
+
+		if var("CI").is_some() {
+			if CI_TRUECOLOR.iter().any(|ci| var(ci).is_some()) {
+				return ColorLevel::TrueColor;
+			}
+			if CI_BASIC.iter().any(|ci| var(ci).is_some())
+				|| var("CI_NAME").as_deref() == Some("codeship")
+			{
+				return ColorLevel::Basic;
+			}
+			return ColorLevel::None;
+		}
+
+		if let Some(colorterm) = var("COLORTERM") {
+			if colorterm == "truecolor" || colorterm == "24bit" {
+				return ColorLevel::TrueColor;
+			}
+		}
+
+		if term.ends_with("-direct") || term.ends_with("-truecolor") {
+			return ColorLevel::TrueColor;
+		}
+
+		match var("TERM_PROGRAM").as_deref() {
+			Some("iTerm.app") | Some("WezTerm") => {
+				return ColorLevel::TrueColor
+			}
+			Some("Apple_Terminal") => return ColorLevel::Ansi256,
+			_ => (),
+		}
+
+		if term.contains("256") {
+			return ColorLevel::Ansi256;
+		}
+
+		if COLOR_TERMS.iter().any(|name| term.starts_with(name))
+			|| var("COLORTERM").is_some()
+		{
+			return ColorLevel::Basic;
+		}
+
+		if cfg!(windows) && term.is_empty() {
+			return ColorLevel::TrueColor;
+		}
+
+		ColorLevel::None
+	}
+
+	/// Checks whether any escape sequences may be written
+	#[inline(always)]
+	pub const fn has_color(self) -> bool {
+		!matches!(self, ColorLevel::None)
+	}
+}
+
+/// Checks whether the terminal shows hyperlinks, without checking whether a
+/// stream is connected to it. This is used when the color level is given by
+/// the caller, who has already decided whether the output is a terminal.
+pub(crate) fn terminal_shows_hyperlinks() -> bool {
+	let cached = TERMINAL_HYPERLINKS.load(Ordering::Relaxed);
+	if cached != UNKNOWN {
+		return cached == 1;
+	}
+
+	let supported = Stream::hyperlinks_from_env(true, |key| env::var(key).ok());
+	TERMINAL_HYPERLINKS.store(supported as u8, Ordering::Relaxed);
+	supported
+}
+
+/// Lets the Windows console understand escape sequences on stdout and
+/// stderr. This is done the first time a chalk is written, so that chalks
+/// can be made in constants.
+pub(crate) fn enable_virtual_terminal() {
+	#[cfg(windows)]
+	{
+		static SETUP: std::sync::Once = std::sync::Once::new();
+		SETUP.call_once(|| {
+			for stream in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+				unsafe {
+					let handle = GetStdHandle(stream);
+					let mut dw_mode: DWORD = 0;
+					// the mode can't be changed if it isn't a console
+					if GetConsoleMode(handle, &mut dw_mode) != 0 {
+						dw_mode |= ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+						SetConsoleMode(handle, dw_mode);
+					}
+				}
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	fn level(is_terminal: bool, vars: &[(&str, &str)]) -> ColorLevel {
+		ColorLevel::from_env(is_terminal, |key| {
+			vars.iter()
+				.find(|(name, _)| *name == key)
+				.map(|(_, value)| value.to_string())
+		})
+	}
+
+	#[test]
+	fn pipes_have_no_color() {
+		assert_eq!(
+			level(false, &[("TERM", "xterm-256color")]),
+			ColorLevel::None
+		);
+		assert_eq!(
+			level(true, &[("TERM", "xterm-256color")]),
+			ColorLevel::Ansi256
+		);
+	}
+
+	#[test]
+	fn environment_overrides() {
+		let term = ("TERM", "xterm");
+		assert_eq!(level(true, &[term, ("NO_COLOR", "1")]), ColorLevel::None);
+		assert_eq!(level(true, &[term, ("CLICOLOR", "0")]), ColorLevel::None);
+		assert_eq!(level(true, &[("TERM", "dumb")]), ColorLevel::None);
+		assert_eq!(
+			level(false, &[("FORCE_COLOR", "3")]),
+			ColorLevel::TrueColor
+		);
+		assert_eq!(level(false, &[("FORCE_COLOR", "")]), ColorLevel::Basic);
+		assert_eq!(
+			level(true, &[term, ("FORCE_COLOR", "0")]),
+			ColorLevel::None
+		);
+		assert_eq!(
+			level(false, &[term, ("CLICOLOR_FORCE", "1")]),
+			ColorLevel::Basic
+		);
+	}
+
+	#[test]
+	fn terminal_capabilities() {
+		let truecolor = [("TERM", "xterm"), ("COLORTERM", "truecolor")];
+		assert_eq!(level(true, &truecolor), ColorLevel::TrueColor);
+		assert_eq!(level(true, &[("TERM", "screen")]), ColorLevel::Basic);
+		let github = [("CI", "true"), ("GITHUB_ACTIONS", "true")];
+		assert_eq!(level(true, &github), ColorLevel::TrueColor);
+		assert_eq!(level(true, &[("CI", "true")]), ColorLevel::None);
+	}
+
+	#[test]
+	fn hyperlinks() {
+		let links = |is_terminal, vars: &[(&str, &str)]| {
+			Stream::hyperlinks_from_env(is_terminal, |key| {
+				vars.iter()
+					.find(|(name, _)| *name == key)
+					.map(|(_, value)| value.to_string())
+			})
+		};
+
+		assert!(links(true, &[("TERM_PROGRAM", "WezTerm")]));
+		assert!(links(true, &[("VTE_VERSION", "6003")]));
+		assert!(!links(true, &[("VTE_VERSION", "4601")]));
+		assert!(!links(false, &[("TERM", "xterm-kitty")]));
+		assert!(!links(true, &[("TERM", "xterm-256color")]));
+		assert!(links(false, &[("FORCE_HYPERLINK", "1")]));
+		assert!(!links(
+			true,
+			&[("WT_SESSION", "x"), ("FORCE_HYPERLINK", "0")]
+		));
+	}
+}
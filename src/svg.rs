@@ -0,0 +1,429 @@
+use crate::width::graphemes;
+use crate::{escape_html, parse_ansi, visible_width, Chalk, Palette, Segment};
+
+use std::borrow::Borrow;
+use std::fmt::Write;
+
+/// The colors of the buttons in the window's title bar
+const BUTTONS: [&str; 3] = ["#ff5f56", "#ffbd2e", "#27c93f"];
+
+/// The number of columns between tab stops
+const TAB_WIDTH: usize = 8;
+
+/// Text on a single line which is drawn in the same style
+struct Run {
+	row: usize,
+	column: usize,
+	columns: usize,
+	chalk: Chalk,
+	text: String,
+}
+
+/// Writes a number with at most two decimal places
+fn number(n: f32) -> String {
+	let n = format!("{:.2}", n);
+	n.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Lays pieces of styled text out on a grid, returning the runs of text
+/// along with the number of rows and columns they take up
+fn layout<C: Borrow<Chalk>, S: AsRef<str>>(
+	spans: impl IntoIterator<Item = (C, S)>,
+) -> (Vec<Run>, usize, usize) {
+	let mut runs: Vec<Run> = Vec::new();
+	let (mut row, mut column, mut columns) = (0, 0, 0);
+
+	for (chalk, text) in spans {
+		let chalk = chalk.borrow();
+		for grapheme in graphemes(text.as_ref()) {
+			let (grapheme, width) = match grapheme {
+				"\n" => {
+					row += 1;
+					column = 0;
+					continue;
+				}
+				"\t" => {
+					let width = TAB_WIDTH - column % TAB_WIDTH;
+					(" ".repeat(width), width)
+				}
+				grapheme => (grapheme.to_string(), visible_width(grapheme)),
+			};
+			if width == 0 {
+				continue;
+			}
+
+			match runs.last_mut() {
+				Some(run)
+					if run.row == row
+						&& run.column + run.columns == column
+						&& run.chalk == *chalk =>
+				{
+					run.text.push_str(&grapheme);
+					run.columns += width;
+				}
+				_ => runs.push(Run {
+					row,
+					column,
+					columns: width,
+					chalk: *chalk,
+					text: grapheme,
+				}),
+			}
+
+			column += width;
+			columns = columns.max(column);
+		}
+	}
+
+	let rows = if runs.is_empty() && row == 0 {
+		0
+	} else {
+		row + 1
+	};
+	(runs, rows, columns)
+}
+
+/// Draws styled text as an SVG image of a terminal.
+///
+/// Text is drawn on a grid of cells, one for each column, with the colors of
+/// basic and ANSI colors taken from a [`Palette`]. The same text always gives
+/// the same image.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::SvgRenderer;
+///
+/// let mut svg = SvgRenderer::new();
+/// svg.title("cargo build");
+/// let image = svg.render("\x1b[1;32mCompiling\x1b[0m chalk_rs");
+/// assert!(image.starts_with("<svg"));
+/// assert!(image.contains(">Compiling</text>"));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SvgRenderer {
+	palette: Palette,
+	chrome: bool,
+	title: Option<String>,
+	font_family: String,
+	font_size: u32,
+}
+
+impl Default for SvgRenderer {
+	fn default() -> Self {
+		SvgRenderer {
+			palette: Palette::default(),
+			chrome: false,
+			title: None,
+			font_family: "ui-monospace, Menlo, Consolas, 'DejaVu Sans Mono', \
+				monospace"
+				.to_string(),
+			font_size: 14,
+		}
+	}
+}
+
+impl SvgRenderer {
+	/// Creates a renderer with xterm's palette, without a window around the
+	/// text
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the colors used for basic and ANSI colors, and for text without
+	/// a color
+	pub fn palette(&mut self, palette: Palette) -> &mut Self {
+		self.palette = palette;
+		self
+	}
+
+	/// Sets whether the text is drawn in a window with a title bar
+	#[inline(always)]
+	pub fn chrome(&mut self, chrome: bool) -> &mut Self {
+		self.chrome = chrome;
+		self
+	}
+
+	/// Draws the text in a window with the given title
+	pub fn title(&mut self, title: &str) -> &mut Self {
+		self.title = Some(title.to_string());
+		self.chrome = true;
+		self
+	}
+
+	/// Sets the CSS font family of the text. This should be a monospace font.
+	pub fn font_family(&mut self, font_family: &str) -> &mut Self {
+		self.font_family = font_family.to_string();
+		self
+	}
+
+	/// Sets the size of the text, in pixels. This is 14 by default.
+	#[inline(always)]
+	pub fn font_size(&mut self, font_size: u32) -> &mut Self {
+		self.font_size = font_size;
+		self
+	}
+
+	/// Draws text containing escape sequences. Escape sequences which aren't
+	/// SGR sequences are ignored.
+	pub fn render(&self, text: &str) -> String {
+		let spans = parse_ansi(text).into_iter().filter_map(|s| match s {
+			Segment::Text(chalk, text) => Some((chalk, text)),
+			_ => None,
+		});
+		self.render_spans(spans)
+	}
+
+	/// Draws pieces of styled text, such as the ones from
+	/// [`crate::Markup::spans`]
+	pub fn render_spans<C: Borrow<Chalk>, S: AsRef<str>>(
+		&self,
+		spans: impl IntoIterator<Item = (C, S)>,
+	) -> String {
+		let mut svg = String::new();
+		// writing to a string can't fail
+		let _ = self.write_svg(&mut svg, spans);
+		svg
+	}
+
+	fn write_svg<C: Borrow<Chalk>, S: AsRef<str>>(
+		&self,
+		w: &mut String,
+		spans: impl IntoIterator<Item = (C, S)>,
+	) -> std::fmt::Result {
+		let (runs, rows, columns) = layout(spans);
+		let size = self.font_size as f32;
+		let cell_width = size * 0.6;
+		let line_height = size * 1.25;
+		let padding = size;
+		let bar = if self.chrome { size * 2.0 } else { 0.0 };
+		let width = columns as f32 * cell_width + padding * 2.0;
+		let height = rows as f32 * line_height + padding * 2.0 + bar;
+		let (width, height) = (number(width), number(height));
+
+		writeln!(
+			w,
+			"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" \
+			height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+			w = width,
+			h = height
+		)?;
+		writeln!(
+			w,
+			"<style>\n\
+			text {{ font-family: {}; font-size: {}px; white-space: pre; }}\n\
+			.b {{ font-weight: bold; }}\n\
+			.i {{ font-style: italic; }}\n\
+			.u {{ text-decoration: underline; }}\n\
+			.uu {{ text-decoration: underline double; }}\n\
+			.d {{ opacity: 0.5; }}\n\
+			</style>",
+			escape_style(&self.font_family),
+			self.font_size
+		)?;
+
+		let corners = if self.chrome { " rx=\"6\"" } else { "" };
+		writeln!(
+			w,
+			"<rect width=\"{}\" height=\"{}\"{} fill=\"{}\"/>",
+			width,
+			height,
+			corners,
+			self.palette.background().to_hex()
+		)?;
+
+		if self.chrome {
+			for (i, color) in BUTTONS.iter().enumerate() {
+				writeln!(
+					w,
+					"<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+					number(padding + size * 1.2 * i as f32),
+					number(size),
+					number(size * 0.4),
+					color
+				)?;
+			}
+			if let Some(title) = &self.title {
+				writeln!(
+					w,
+					"<text x=\"50%\" y=\"{}\" text-anchor=\"middle\" \
+					fill=\"{}\" class=\"d\">{}</text>",
+					number(size * 1.35),
+					self.palette.foreground().to_hex(),
+					escape_html(title)
+				)?;
+			}
+		}
+
+		writeln!(
+			w,
+			"<g transform=\"translate({} {})\">",
+			number(padding),
+			number(padding + bar)
+		)?;
+
+		for run in &runs {
+			let chalk = &run.chalk;
+			let foreground = self.palette.resolve(&chalk.foreground);
+			let background = self.palette.resolve(&chalk.background);
+			let (foreground, background) = if chalk.is_inverted() {
+				(
+					background.unwrap_or_else(|| self.palette.background()),
+					foreground.or_else(|| Some(self.palette.foreground())),
+				)
+			} else {
+				(
+					foreground.unwrap_or_else(|| self.palette.foreground()),
+					background,
+				)
+			};
+
+			let x = number(run.column as f32 * cell_width);
+			if let Some(background) = background {
+				writeln!(
+					w,
+					"<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+					fill=\"{}\"/>",
+					x,
+					number(run.row as f32 * line_height),
+					number(run.columns as f32 * cell_width),
+					number(line_height),
+					background.to_hex()
+				)?;
+			}
+
+			if chalk.is_hidden() || run.text.trim().is_empty() {
+				continue;
+			}
+
+			let mut classes = Vec::new();
+			if chalk.is_bold() {
+				classes.push("b");
+			}
+			if chalk.is_dim() {
+				classes.push("d");
+			}
+			if chalk.is_italicized() {
+				classes.push("i");
+			}
+
+			// lines other than a plain underline are drawn with an inline
+			// style, since they can be combined
+			let underline = [
+				(chalk.is_single_underlined(), "underline"),
+				(chalk.is_double_underlined(), "underline double"),
+				(chalk.is_curly_underlined(), "underline wavy"),
+				(chalk.is_dotted_underlined(), "underline dotted"),
+				(chalk.is_dashed_underlined(), "underline dashed"),
+			];
+			let underline = underline.iter().find(|(set, _)| *set);
+			let lines: Vec<&str> = underline
+				.map(|(_, line)| *line)
+				.into_iter()
+				.chain(chalk.is_struck_through().then_some("line-through"))
+				.chain(chalk.is_overlined().then_some("overline"))
+				.collect();
+			let underline_color = self.palette.resolve(&chalk.underline_color);
+			let mut style = None;
+			match lines.as_slice() {
+				["underline"] if underline_color.is_none() => classes.push("u"),
+				["underline double"] if underline_color.is_none() => {
+					classes.push("uu")
+				}
+				[] => (),
+				lines => {
+					let mut decoration =
+						format!("text-decoration: {}", lines.join(" "));
+					if let Some(color) = underline_color {
+						let color = color.to_hex();
+						let _ = write!(decoration, " {}", color);
+					}
+					style = Some(decoration);
+				}
+			}
+
+			write!(
+				w,
+				"<text x=\"{}\" y=\"{}\" fill=\"{}\"",
+				x,
+				number(run.row as f32 * line_height + size),
+				foreground.to_hex()
+			)?;
+			if !classes.is_empty() {
+				write!(w, " class=\"{}\"", classes.join(" "))?;
+			}
+			if let Some(style) = style {
+				write!(w, " style=\"{}\"", style)?;
+			}
+			writeln!(
+				w,
+				" textLength=\"{}\">{}</text>",
+				number(run.columns as f32 * cell_width),
+				escape_html(&run.text)
+			)?;
+		}
+
+		w.write_str("</g>\n</svg>\n")
+	}
+}
+
+/// Escapes the characters which end text in a stylesheet. Quotes are left
+/// alone, since they're part of CSS.
+fn escape_style(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn snapshot() {
+		let mut palette = Palette::default();
+		palette.set(1, crate::RgbColor::new(255, 0, 0));
+		let mut svg = SvgRenderer::new();
+		svg.palette(palette).font_family("mono").font_size(10);
+
+		let image = svg.render("\x1b[31;1ma\x1b[7m<\x1b[0m\x1b[2K\n\tb");
+		let expected = concat!(
+			"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"74\" ",
+			"height=\"45\" viewBox=\"0 0 74 45\">\n",
+			"<style>\n",
+			"text { font-family: mono; font-size: 10px; white-space: pre; }\n",
+			".b { font-weight: bold; }\n",
+			".i { font-style: italic; }\n",
+			".u { text-decoration: underline; }\n",
+			".uu { text-decoration: underline double; }\n",
+			".d { opacity: 0.5; }\n",
+			"</style>\n",
+			"<rect width=\"74\" height=\"45\" fill=\"#000000\"/>\n",
+			"<g transform=\"translate(10 10)\">\n",
+			"<text x=\"0\" y=\"10\" fill=\"#ff0000\" class=\"b\" ",
+			"textLength=\"6\">a</text>\n",
+			"<rect x=\"6\" y=\"0\" width=\"6\" height=\"12.5\" ",
+			"fill=\"#ff0000\"/>\n",
+			"<text x=\"6\" y=\"10\" fill=\"#000000\" class=\"b\" ",
+			"textLength=\"6\">&lt;</text>\n",
+			"<text x=\"0\" y=\"22.5\" fill=\"#c0c0c0\" ",
+			"textLength=\"54\">        b</text>\n",
+			"</g>\n",
+			"</svg>\n",
+		);
+		assert_eq!(image, expected);
+
+		svg.title("a & b");
+		let image = svg.render("\x1b[4:3;9;58;5;9mx");
+		assert!(image.contains(
+			" style=\"text-decoration: underline wavy line-through #ff0000\""
+		));
+		assert_eq!(image.matches("<circle").count(), 3);
+		assert!(image.contains("class=\"d\">a &amp; b</text>"));
+
+		svg.font_family("'DejaVu Sans Mono', \"Fira Code\" </style>");
+		let image = svg.render("");
+		assert!(image.contains(
+			"font-family: 'DejaVu Sans Mono', \"Fira Code\" &lt;/style>;"
+		));
+	}
+}
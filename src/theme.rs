@@ -0,0 +1,301 @@
+use crate::{Chalk, ChalkParseError};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The styles every theme starts with
+const DEFAULTS: [(&str, &str); 14] = [
+	("error", "bold red"),
+	("warning", "bold yellow"),
+	("info", "bold cyan"),
+	("success", "bold green"),
+	("note", "bold"),
+	("hint", "dim"),
+	("heading", "bold underline"),
+	("emphasis", "bold"),
+	("muted", "dim"),
+	("path", "cyan"),
+	("url", "underline blue"),
+	("code", "yellow"),
+	("number", "magenta"),
+	("string", "green"),
+];
+
+/// The reason a theme couldn't be loaded
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ThemeErrorKind {
+	/// The theme file couldn't be read
+	Io(io::ErrorKind),
+	/// A line which isn't a section, a comment, or `name = style`
+	InvalidLine,
+	/// A style which couldn't be parsed
+	Style(ChalkParseError),
+}
+
+/// An error from loading a [`Theme`]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ThemeError {
+	kind: ThemeErrorKind,
+	line: usize,
+}
+
+impl ThemeError {
+	/// What went wrong
+	pub fn kind(&self) -> &ThemeErrorKind {
+		&self.kind
+	}
+
+	/// The line the error was found on, starting from 1, or 0 if the file
+	/// couldn't be read
+	pub const fn line(&self) -> usize {
+		self.line
+	}
+}
+
+impl Display for ThemeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.kind {
+			ThemeErrorKind::Io(kind) => {
+				return write!(f, "couldn't read theme: {}", kind)
+			}
+			ThemeErrorKind::InvalidLine => {
+				f.write_str("expected `name = style`")?
+			}
+			ThemeErrorKind::Style(error) => write!(f, "{}", error)?,
+		}
+
+		write!(f, " on line {}", self.line)
+	}
+}
+
+impl Error for ThemeError {}
+
+/// A set of styles, referred to by what they're used for rather than how
+/// they look.
+///
+/// Names are made of parts separated by dots, and each name inherits the
+/// styles of the names before it. For example, `error.code` is drawn with the
+/// style of `error`, with the style of `error.code` on top of it.
+///
+/// Themes are written in a format like INI, with one `name = style` per
+/// line, where the style is parsed the same way as a [`Chalk`], optionally in
+/// quotes. A `[section]` header is put in front of the names after it. Lines
+/// starting with `#` or `;` are comments.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{Chalk, Theme};
+///
+/// let theme: Theme = "
+/// 	error = \"bold red on #222\"
+/// 	[error]
+/// 	code = underline
+/// "
+/// .parse()
+/// .unwrap();
+///
+/// let expected: Chalk = "bold underline red on #222".parse().unwrap();
+/// assert_eq!(theme.get("error.code"), expected);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Theme {
+	styles: HashMap<String, Chalk>,
+}
+
+impl Theme {
+	/// Creates a theme without any styles
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a theme with the built-in styles, such as `error`, `warning`,
+	/// and `path`
+	pub fn builtin() -> Self {
+		let mut theme = Self::new();
+		for (name, style) in DEFAULTS.iter() {
+			// the built-in styles are known to be valid
+			if let Ok(chalk) = style.parse() {
+				theme.insert(name, chalk);
+			}
+		}
+
+		theme
+	}
+
+	/// Parses a theme from its text
+	pub fn parse(source: &str) -> Result<Self, ThemeError> {
+		let mut theme = Self::new();
+		let mut section = String::new();
+
+		for (i, line) in source.lines().enumerate() {
+			let error = |kind| ThemeError { kind, line: i + 1 };
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') || line.starts_with(';')
+			{
+				continue;
+			}
+
+			if let Some(name) =
+				line.strip_prefix('[').and_then(|l| l.strip_suffix(']'))
+			{
+				section = name.trim().to_string();
+				continue;
+			}
+
+			let Some((name, style)) = line.split_once('=') else {
+				return Err(error(ThemeErrorKind::InvalidLine));
+			};
+			let name = name.trim().trim_matches('"');
+			let style = style.trim();
+			let style = style
+				.strip_prefix('"')
+				.and_then(|s| s.strip_suffix('"'))
+				.or_else(|| {
+					style.strip_prefix('\'').and_then(|s| s.strip_suffix('\''))
+				})
+				.unwrap_or(style);
+			if name.is_empty() {
+				return Err(error(ThemeErrorKind::InvalidLine));
+			}
+
+			let chalk =
+				style.parse().map_err(|e| error(ThemeErrorKind::Style(e)))?;
+			if section.is_empty() {
+				theme.insert(name, chalk);
+			} else {
+				theme.insert(&format!("{}.{}", section, name), chalk);
+			}
+		}
+
+		Ok(theme)
+	}
+
+	/// Reads a theme from a file
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ThemeError> {
+		let source = std::fs::read_to_string(path).map_err(|e| ThemeError {
+			kind: ThemeErrorKind::Io(e.kind()),
+			line: 0,
+		})?;
+		Self::parse(&source)
+	}
+
+	/// The path of the user's theme file.
+	///
+	/// This is `chalk_rs/theme.conf` in `%APPDATA%` on Windows, and in
+	/// `$XDG_CONFIG_HOME` or `~/.config` everywhere else.
+	pub fn user_path() -> Option<PathBuf> {
+		let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+		let base = if cfg!(windows) {
+			var("APPDATA").map(PathBuf::from)
+		} else {
+			var("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| {
+				var("HOME").map(|home| PathBuf::from(home).join(".config"))
+			})
+		};
+
+		base.map(|base| base.join("chalk_rs").join("theme.conf"))
+	}
+
+	/// Loads the built-in theme, with the user's theme on top of it, if they
+	/// have one
+	pub fn load() -> Result<Self, ThemeError> {
+		let mut theme = Self::builtin();
+		if let Some(path) = Self::user_path().filter(|path| path.is_file()) {
+			theme.extend(Self::from_file(path)?);
+		}
+
+		Ok(theme)
+	}
+
+	/// Sets the style for a name
+	pub fn insert(&mut self, name: &str, chalk: Chalk) -> &mut Self {
+		self.styles.insert(name.to_string(), chalk);
+		self
+	}
+
+	/// Removes the style for a name, returning it if there was one
+	pub fn remove(&mut self, name: &str) -> Option<Chalk> {
+		self.styles.remove(name)
+	}
+
+	/// Replaces the styles in this theme with the ones in another theme
+	pub fn extend(&mut self, other: Theme) -> &mut Self {
+		self.styles.extend(other.styles);
+		self
+	}
+
+	/// Checks whether the theme has a style for the name, not including the
+	/// styles it inherits
+	pub fn contains(&self, name: &str) -> bool {
+		self.styles.contains_key(name)
+	}
+
+	/// The names which have styles
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.styles.keys().map(String::as_str)
+	}
+
+	/// Gets the chalk for a name, including the styles it inherits. A name
+	/// without any styles gives an empty chalk.
+	pub fn get(&self, name: &str) -> Chalk {
+		let mut chalk = Chalk::default();
+		let ends = name.match_indices('.').map(|(i, _)| i);
+		for end in ends.chain(std::iter::once(name.len())) {
+			if let Some(style) = self.styles.get(&name[..end]) {
+				chalk.layer(style);
+			}
+		}
+
+		chalk
+	}
+}
+
+impl FromStr for Theme {
+	type Err = ThemeError;
+
+	fn from_str(source: &str) -> Result<Self, Self::Err> {
+		Self::parse(source)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn inheritance() {
+		let mut theme = Theme::builtin();
+		theme.insert("error.code", Chalk::new().with_yellow());
+		assert_eq!(
+			theme.get("error.code"),
+			Chalk::new().bold().yellow().clone()
+		);
+		assert_eq!(theme.get("error.x.y"), Chalk::new().bold().red().clone());
+		assert_eq!(theme.get("missing"), Chalk::new());
+
+		let user: Theme = "[error]\ncode = 'dim bg_blue'".parse().unwrap();
+		theme.extend(user);
+		assert_eq!(
+			theme.get("error.code"),
+			Chalk::new().dim().red().bg_blue().clone()
+		);
+	}
+
+	#[test]
+	fn errors() {
+		let error = Theme::parse("# ok\na = red\nb = red purpel").unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"unknown color or style `purpel` on line 3"
+		);
+		let error = Theme::parse("[a]\nbold").unwrap_err();
+		assert_eq!(error.kind(), &ThemeErrorKind::InvalidLine);
+		assert_eq!(error.to_string(), "expected `name = style` on line 2");
+	}
+}
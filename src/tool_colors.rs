@@ -0,0 +1,389 @@
+use crate::{
+	BasicColor, Chalk, ChalkParseError, ChalkParseErrorKind, ChalkType,
+};
+
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+
+/// The colors grep uses when `GREP_COLORS` doesn't set them
+const GREP_DEFAULTS: &str = "ms=01;31:mc=01;31:sl=:cx=:fn=35:ln=32:bn=32:se=36";
+
+/// The colors GCC uses when `GCC_COLORS` isn't set
+const GCC_DEFAULTS: &str = "error=01;31:warning=01;35:note=01;36:range1=32:\
+	range2=34:locus=01:quote=01:path=01;36:fixit-insert=32:fixit-delete=31:\
+	diff-filename=01:diff-hunk=32:diff-delete=31:diff-insert=32:\
+	type-diff=01;32:fnname=01;32:targs=35";
+
+/// The colors in git's color syntax, in the order of their xterm indices
+const GIT_COLORS: [&str; 8] = [
+	"black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+type Setter = fn(&mut Chalk) -> &mut Chalk;
+
+/// The attributes in git's color syntax, and how to turn them on and off
+const GIT_ATTRIBUTES: [(&str, Setter, Setter); 7] = [
+	("bold", Chalk::bold, Chalk::reset_weight),
+	("dim", Chalk::dim, Chalk::reset_weight),
+	("ul", Chalk::underline, Chalk::no_underline),
+	("blink", Chalk::blink, Chalk::stop_blink),
+	("reverse", Chalk::invert, Chalk::uninvert),
+	("italic", Chalk::italic, Chalk::unitalic),
+	("strike", Chalk::strikethrough, Chalk::no_strikethrough),
+];
+
+/// Parses a list of `name=sgr` entries separated by colons, such as
+/// `error=01;31:note=01;36`.
+///
+/// This is the format of `LS_COLORS`, `GREP_COLORS`, and `GCC_COLORS`.
+/// Entries which aren't valid, or which don't have a value, are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{parse_color_list, Chalk};
+///
+/// let colors = parse_color_list("error=01;31:rv:note=01;36");
+/// assert_eq!(colors["error"], Chalk::new().with_bold().with_red());
+/// assert!(!colors.contains_key("rv"));
+/// ```
+pub fn parse_color_list(value: &str) -> HashMap<String, Chalk> {
+	value
+		.split(':')
+		.filter_map(|entry| entry.split_once('='))
+		.filter_map(|(name, sgr)| {
+			Some((name.to_string(), Chalk::from_sgr(sgr).ok()?))
+		})
+		.collect()
+}
+
+/// Gets the colors grep uses, from `GREP_COLORS`, keyed by their names, such
+/// as `ms` for matched text and `fn` for file names
+pub fn grep_colors() -> HashMap<String, Chalk> {
+	let mut colors = parse_color_list(GREP_DEFAULTS);
+	if let Ok(value) = std::env::var("GREP_COLORS") {
+		colors.extend(parse_color_list(&value));
+	}
+
+	colors
+}
+
+/// Gets the colors GCC uses for diagnostics, from `GCC_COLORS`, keyed by
+/// their names, such as `error` and `locus`. If `GCC_COLORS` is set but
+/// empty, there are no colors.
+pub fn gcc_colors() -> HashMap<String, Chalk> {
+	match std::env::var("GCC_COLORS") {
+		Ok(value) => parse_color_list(&value),
+		Err(_) => parse_color_list(GCC_DEFAULTS),
+	}
+}
+
+/// Parses one of git's color words, which is `normal`, `default`, a color
+/// name which may start with `bright`, a number from 0 to 255, or a hex code
+fn parse_git_word(word: &str) -> Option<Option<ChalkType>> {
+	match word {
+		"normal" => return Some(None),
+		"default" => return Some(Some(ChalkType::Default)),
+		_ => (),
+	}
+
+	let (bright, name) = match word.strip_prefix("bright") {
+		Some(name) => (8, name),
+		None => (0, word),
+	};
+	if let Some(i) = GIT_COLORS.iter().position(|n| *n == name) {
+		let color = BasicColor::from_index(i as u8 + bright);
+		return Some(Some(ChalkType::Basic(color)));
+	}
+
+	if let Ok(n) = word.parse() {
+		return Some(Some(ChalkType::ansi(n)));
+	}
+
+	let hex = word.strip_prefix('#')?;
+	if hex.len() != 3 && hex.len() != 6 {
+		return None;
+	}
+	word.parse().ok().map(|color| Some(ChalkType::Rgb(color)))
+}
+
+/// Parses a color in the syntax of git's `color.*` settings, such as
+/// `blue black bold` or `#ff0000 ul`.
+///
+/// The first color is the foreground and the second is the background. The
+/// attributes are `bold`, `dim`, `ul`, `blink`, `reverse`, `italic`, and
+/// `strike`, and any of them can be turned off by putting `no` or `no-` in
+/// front of it.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{parse_git_color, Chalk};
+///
+/// let chalk = parse_git_color("brightred normal ul").unwrap();
+/// assert_eq!(chalk, Chalk::new().with_light_red().with_underline());
+/// assert_eq!(parse_git_color("blue black bold").unwrap().spec(), "bold blue on black");
+/// assert_eq!(parse_git_color("bold blod").unwrap_err().token(), "blod");
+/// ```
+pub fn parse_git_color(value: &str) -> Result<Chalk, ChalkParseError> {
+	let mut chalk = Chalk::default();
+	let mut colors = 0;
+
+	for word in value.split_whitespace() {
+		let lower = word.to_ascii_lowercase();
+		let attribute = lower.strip_prefix("no").map(|name| {
+			let name = name.strip_prefix('-').unwrap_or(name);
+			(name, false)
+		});
+		let attribute = attribute
+			.into_iter()
+			.chain(std::iter::once((lower.as_str(), true)))
+			.find_map(|(name, on)| {
+				let (_, set, unset) =
+					GIT_ATTRIBUTES.iter().find(|(n, _, _)| *n == name)?;
+				Some(if on { set } else { unset })
+			});
+
+		if let Some(set) = attribute {
+			set(&mut chalk);
+		} else if lower == "reset" {
+			chalk = Chalk::default();
+		} else if let Some(color) = parse_git_word(&lower) {
+			let slot = match colors {
+				0 => &mut chalk.foreground,
+				1 => &mut chalk.background,
+				_ => {
+					return Err(ChalkParseError::new(
+						ChalkParseErrorKind::ExtraColor,
+						word,
+					))
+				}
+			};
+			if let Some(color) = color {
+				*slot = color;
+			}
+			colors += 1;
+		} else {
+			return Err(ChalkParseError::new(
+				ChalkParseErrorKind::UnknownToken,
+				word,
+			));
+		}
+	}
+
+	Ok(chalk)
+}
+
+/// The styles `ls` gives to files, parsed from `LS_COLORS`.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{Chalk, LsColors};
+///
+/// let colors = LsColors::parse("di=01;34:ex=01;32:*.tar=01;31:*.TAR=31");
+/// assert_eq!(colors.get("di"), Some(&Chalk::new().with_bold().with_blue()));
+/// assert_eq!(
+/// 	colors.for_extension("backup.tar"),
+/// 	Some(&Chalk::new().with_bold().with_red())
+/// );
+/// assert_eq!(
+/// 	colors.for_extension("BACKUP.Tar"),
+/// 	Some(&Chalk::new().with_red())
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LsColors {
+	/// Styles for kinds of files, keyed by their two letter codes
+	kinds: HashMap<String, Chalk>,
+	/// Styles for the endings of file names, such as `.tar`
+	patterns: Vec<(String, Chalk)>,
+	/// Whether links are styled like the file they point to
+	link_target: bool,
+}
+
+impl LsColors {
+	/// Parses the value of `LS_COLORS`
+	pub fn parse(value: &str) -> Self {
+		let mut colors = Self::default();
+		for (name, sgr) in value.split(':').filter_map(|e| e.split_once('=')) {
+			if name == "ln" && sgr == "target" {
+				colors.link_target = true;
+				continue;
+			}
+
+			let Ok(chalk) = Chalk::from_sgr(sgr) else {
+				continue;
+			};
+			match name.strip_prefix('*') {
+				Some(pattern) => {
+					colors.patterns.push((pattern.to_string(), chalk))
+				}
+				None => {
+					colors.kinds.insert(name.to_string(), chalk);
+				}
+			}
+		}
+
+		colors
+	}
+
+	/// Parses `LS_COLORS`, if it's set
+	pub fn from_env() -> Option<Self> {
+		std::env::var("LS_COLORS")
+			.ok()
+			.map(|value| Self::parse(&value))
+	}
+
+	/// Gets the style for a kind of file, given by its code, such as `di`
+	/// for directories or `ex` for executables
+	pub fn get(&self, kind: &str) -> Option<&Chalk> {
+		self.kinds.get(kind)
+	}
+
+	/// Gets the style for a file name from its ending, such as `.tar`.
+	///
+	/// The pattern which was given last is used. An exact match is preferred
+	/// over one which only matches when ignoring case.
+	pub fn for_extension(&self, name: &str) -> Option<&Chalk> {
+		let ends_with = |pattern: &str| {
+			name.len() >= pattern.len()
+				&& name.is_char_boundary(name.len() - pattern.len())
+				&& name[name.len() - pattern.len()..]
+					.eq_ignore_ascii_case(pattern)
+		};
+
+		let patterns = self.patterns.iter().rev();
+		patterns
+			.clone()
+			.find(|(pattern, _)| name.ends_with(pattern.as_str()))
+			.or_else(|| {
+				patterns.clone().find(|(pattern, _)| ends_with(pattern))
+			})
+			.map(|(_, chalk)| chalk)
+	}
+
+	/// The code for the kind of a file which isn't a link
+	fn kind(metadata: &Metadata) -> &'static str {
+		let file_type = metadata.file_type();
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+			let mode = metadata.permissions().mode();
+			let (sticky, other_writable) =
+				(mode & 0o1000 != 0, mode & 0o002 != 0);
+			if file_type.is_dir() {
+				return match (sticky, other_writable) {
+					(true, true) => "tw",
+					(false, true) => "ow",
+					(true, false) => "st",
+					(false, false) => "di",
+				};
+			} else if file_type.is_fifo() {
+				return "pi";
+			} else if file_type.is_socket() {
+				return "so";
+			} else if file_type.is_block_device() {
+				return "bd";
+			} else if file_type.is_char_device() {
+				return "cd";
+			} else if mode & 0o4000 != 0 {
+				return "su";
+			} else if mode & 0o2000 != 0 {
+				return "sg";
+			} else if mode & 0o111 != 0 {
+				return "ex";
+			}
+		}
+
+		if file_type.is_dir() {
+			"di"
+		} else {
+			"fi"
+		}
+	}
+
+	/// Gets the style `ls` would give the file at a path.
+	///
+	/// This looks at the kind of file first, such as a directory, a link, or
+	/// an executable. Only regular files are styled by their extension.
+	pub fn for_path(&self, path: impl AsRef<Path>) -> Option<&Chalk> {
+		let path = path.as_ref();
+		let Ok(metadata) = path.symlink_metadata() else {
+			return self.get("mi");
+		};
+
+		let metadata = if metadata.file_type().is_symlink() {
+			match path.metadata() {
+				Ok(target) if self.link_target => target,
+				Ok(_) => return self.get("ln"),
+				Err(_) => return self.get("or").or_else(|| self.get("ln")),
+			}
+		} else {
+			metadata
+		};
+
+		let kind = Self::kind(&metadata);
+		if kind != "fi" {
+			return self.get(kind).or_else(|| self.get("fi"));
+		}
+
+		let name = path.file_name().map(|name| name.to_string_lossy());
+		name.and_then(|name| self.for_extension(&name))
+			.or_else(|| self.get("fi"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn color_lists() {
+		let colors = parse_color_list("ms=01;31:sl=:bad=99;x:ne");
+		assert_eq!(colors.len(), 2);
+		assert_eq!(colors["sl"], Chalk::new());
+		assert_eq!(colors["ms"], Chalk::new().with_bold().with_red());
+		let defaults = parse_color_list(GREP_DEFAULTS);
+		assert_eq!(defaults["fn"], Chalk::new().with_magenta());
+	}
+
+	#[test]
+	fn git_colors() {
+		let chalk = parse_git_color("#ff0000 ul nobold no-italic").unwrap();
+		assert_eq!(chalk, Chalk::new().with_rgb(255, 0, 0).with_underline());
+		let chalk = parse_git_color("normal 17 reverse").unwrap();
+		assert_eq!(chalk, Chalk::new().with_bg_ansi(17).with_invert());
+		let error = parse_git_color("red blue green").unwrap_err();
+		assert_eq!(error.kind(), ChalkParseErrorKind::ExtraColor);
+		assert_eq!(error.token(), "green");
+	}
+
+	#[test]
+	fn paths() {
+		let colors = LsColors::parse("di=01;34:fi=0:ex=01;32:*.rs=33:mi=05");
+		let nanos = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let name =
+			format!("chalk_rs_ls_colors_{}_{}", std::process::id(), nanos);
+		let dir = std::env::temp_dir().join(name);
+		std::fs::create_dir(&dir).unwrap();
+		std::fs::write(dir.join("lib.rs"), "").unwrap();
+		std::fs::write(dir.join("notes"), "").unwrap();
+
+		assert_eq!(colors.for_path(&dir), colors.get("di"));
+		let rust = Some(&Chalk::new().with_yellow());
+		assert_eq!(colors.for_path(dir.join("lib.rs")), rust);
+		assert_eq!(colors.for_path(dir.join("notes")), colors.get("fi"));
+		assert_eq!(
+			colors.for_path(dir.join("missing")),
+			Some(&Chalk::new().with_blink())
+		);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
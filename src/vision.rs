@@ -0,0 +1,275 @@
+use crate::{Chalk, ChalkType, ColorLevel, Oklab, Palette, RgbColor};
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+const NONE: u8 = u8::MAX;
+
+static GLOBAL: AtomicU8 = AtomicU8::new(NONE);
+
+/// How linear RGB is seen with each deficiency, from Machado, Oliveira and
+/// Fernandes (2009)
+const SIMULATIONS: [[[f32; 3]; 3]; 3] = [
+	[
+		[0.152286, 1.052583, -0.204868],
+		[0.114503, 0.786281, 0.099216],
+		[-0.003882, -0.048116, 1.051998],
+	],
+	[
+		[0.367322, 0.860646, -0.227968],
+		[0.280085, 0.672501, 0.047413],
+		[-0.01182, 0.04294, 0.968881],
+	],
+	[
+		[1.255528, -0.076749, -0.178779],
+		[-0.078411, 0.930809, 0.147602],
+		[0.004733, 0.691367, 0.3039],
+	],
+];
+
+/// How much of the axis a deficiency can't see is added to the one it can
+const HUE_SHIFT: f32 = 1.0;
+/// How much of the axis a deficiency can't see is moved into the lightness
+const LIGHTNESS_SHIFT: f32 = 0.3;
+
+/// A type of color blindness
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Deficiency {
+	/// No red cones, so red looks dark and is confused with green
+	Protanopia,
+	/// No green cones, so red and green are confused
+	Deuteranopia,
+	/// No blue cones, so blue is confused with green and yellow with pink
+	Tritanopia,
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], [r, g, b]: [f32; 3]) -> [f32; 3] {
+	let row = |[x, y, z]: [f32; 3]| x * r + y * g + z * b;
+	[row(matrix[0]), row(matrix[1]), row(matrix[2])]
+}
+
+/// xterm's palette, which is used for basic and ANSI colors when every chalk
+/// is daltonized
+fn xterm() -> &'static Palette {
+	static XTERM: OnceLock<Palette> = OnceLock::new();
+	XTERM.get_or_init(Palette::xterm)
+}
+
+impl Deficiency {
+	const fn from_u8(deficiency: u8) -> Option<Self> {
+		match deficiency {
+			0 => Some(Deficiency::Protanopia),
+			1 => Some(Deficiency::Deuteranopia),
+			2 => Some(Deficiency::Tritanopia),
+			_ => None,
+		}
+	}
+
+	/// Daltonizes every chalk when it's rendered, or stops if `None` is
+	/// given.
+	///
+	/// Basic and ANSI colors are looked up in xterm's palette and replaced
+	/// with RGB colors, which are then shown as closely as the terminal
+	/// allows. This only has an effect at [`ColorLevel::Ansi256`] and
+	/// [`ColorLevel::TrueColor`], since the shifted colors would mostly
+	/// round back to the same basic colors, so chalks are rendered as they
+	/// are at [`ColorLevel::Basic`].
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Chalk, ColorLevel, Deficiency};
+	///
+	/// let mut chalk = Chalk::new();
+	/// chalk.rgb(0, 255, 0);
+	/// Deficiency::set_global(Some(Deficiency::Deuteranopia));
+	/// assert_ne!(chalk.render_at(ColorLevel::TrueColor), "\x1b[38;2;0;255;0m");
+	/// Deficiency::set_global(None);
+	/// assert_eq!(chalk.render_at(ColorLevel::TrueColor), "\x1b[38;2;0;255;0m");
+	///
+	/// let mut red = Chalk::new();
+	/// red.red();
+	/// Deficiency::set_global(Some(Deficiency::Protanopia));
+	/// assert_ne!(red.render_at(ColorLevel::Ansi256), "\x1b[31m");
+	/// assert_eq!(red.render_at(ColorLevel::Basic), "\x1b[31m");
+	/// ```
+	pub fn set_global(deficiency: Option<Deficiency>) {
+		let deficiency = deficiency.map_or(NONE, |d| d as u8);
+		GLOBAL.store(deficiency, Ordering::Relaxed);
+	}
+
+	/// The deficiency which every chalk is daltonized for, if any
+	pub fn global() -> Option<Self> {
+		Self::from_u8(GLOBAL.load(Ordering::Relaxed))
+	}
+}
+
+impl RgbColor {
+	/// Simulates how the color looks to someone with a color deficiency
+	///
+	/// # Example
+	///
+	/// ```rust
+	/// use chalk_rs::{Deficiency, RgbColor};
+	///
+	/// let orange = RgbColor::new(255, 128, 0);
+	/// let lime = RgbColor::new(128, 204, 0);
+	/// let deuteranopia = Deficiency::Deuteranopia;
+	/// let orange_seen = orange.simulate(deuteranopia);
+	/// assert!(orange_seen.delta_e_ok(lime.simulate(deuteranopia)) < 0.05);
+	/// ```
+	pub fn simulate(self, deficiency: Deficiency) -> RgbColor {
+		let matrix = &SIMULATIONS[deficiency as usize];
+		RgbColor::from_linear(multiply(matrix, self.to_linear()))
+	}
+
+	/// Shifts the color so that someone with a color deficiency can tell it
+	/// apart from colors which would otherwise look the same to them.
+	///
+	/// The part of the color on the axis they can't see, red to green or
+	/// blue to yellow in OKLab, is copied onto the other axis and into the
+	/// lightness. Grays don't change.
+	pub fn daltonize(self, deficiency: Deficiency) -> RgbColor {
+		let Oklab { l, a, b } = Oklab::from(self);
+		let lab = match deficiency {
+			Deficiency::Protanopia | Deficiency::Deuteranopia => {
+				Oklab::new(l - LIGHTNESS_SHIFT * a, a, b + HUE_SHIFT * a)
+			}
+			Deficiency::Tritanopia => {
+				Oklab::new(l - LIGHTNESS_SHIFT * b, a - HUE_SHIFT * b, b)
+			}
+		};
+
+		RgbColor::from(lab)
+	}
+}
+
+impl Palette {
+	/// Simulates how every color in the palette looks to someone with a
+	/// color deficiency
+	pub fn simulate(&self, deficiency: Deficiency) -> Palette {
+		self.map(|color| color.simulate(deficiency))
+	}
+
+	/// Daltonizes every color in the palette
+	pub fn daltonize(&self, deficiency: Deficiency) -> Palette {
+		self.map(|color| color.daltonize(deficiency))
+	}
+}
+
+/** Color deficiencies */
+impl Chalk {
+	/// Replaces each color which is set with an RGB color
+	fn map_colors(
+		&self,
+		palette: &Palette,
+		map: impl Fn(RgbColor) -> RgbColor,
+	) -> Chalk {
+		let mut chalk = *self;
+		for color in [
+			&mut chalk.foreground,
+			&mut chalk.background,
+			&mut chalk.underline_color,
+		] {
+			if let Some(rgb) = palette.resolve(color) {
+				*color = ChalkType::Rgb(map(rgb));
+			}
+		}
+
+		chalk
+	}
+
+	/// Simulates how the chalk's colors look to someone with a color
+	/// deficiency. Basic and ANSI colors are looked up in the palette.
+	pub fn simulate(&self, deficiency: Deficiency, palette: &Palette) -> Chalk {
+		self.map_colors(palette, |color| color.simulate(deficiency))
+	}
+
+	/// Daltonizes the chalk's colors. Basic and ANSI colors are looked up in
+	/// the palette.
+	pub fn daltonize(
+		&self,
+		deficiency: Deficiency,
+		palette: &Palette,
+	) -> Chalk {
+		self.map_colors(palette, |color| color.daltonize(deficiency))
+	}
+
+	/// Gets the chalk to render at the given level, daltonized if
+	/// [`Deficiency::set_global`] has been used
+	#[inline(always)]
+	pub(crate) fn for_global_deficiency(
+		&self,
+		level: ColorLevel,
+	) -> Option<Chalk> {
+		self.for_deficiency(Deficiency::global(), level)
+	}
+
+	/// Daltonizes the chalk for rendering, unless the level is too low for
+	/// the shifted colors to be told apart
+	fn for_deficiency(
+		&self,
+		deficiency: Option<Deficiency>,
+		level: ColorLevel,
+	) -> Option<Chalk> {
+		deficiency
+			.filter(|_| level > ColorLevel::Basic)
+			.map(|deficiency| self.daltonize(deficiency, xterm()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn simulation() {
+		let white = RgbColor::new(255, 255, 255);
+		let black = RgbColor::new(0, 0, 0);
+		for deficiency in [
+			Deficiency::Protanopia,
+			Deficiency::Deuteranopia,
+			Deficiency::Tritanopia,
+		] {
+			assert!(white.simulate(deficiency).delta_e_ok(white) < 0.02);
+			assert_eq!(black.simulate(deficiency), black);
+			assert_eq!(white.daltonize(deficiency), white);
+		}
+	}
+
+	#[test]
+	fn daltonization() {
+		let deuteranopia = Deficiency::Deuteranopia;
+		let palette = Palette::xterm();
+		let mut pass = Chalk::new();
+		pass.green();
+		let mut fail = Chalk::new();
+		fail.red();
+
+		let seen = |chalk: &Chalk| {
+			let color = palette.resolve(&chalk.foreground).unwrap();
+			color.simulate(deuteranopia)
+		};
+		let before = seen(&pass).delta_e_ok(seen(&fail));
+		let pass = pass.daltonize(deuteranopia, &palette);
+		let fail = fail.daltonize(deuteranopia, &palette);
+		let after = seen(&pass).delta_e_ok(seen(&fail));
+		assert!(after > before * 1.5, "{} {}", before, after);
+		assert!(matches!(pass.foreground, ChalkType::Rgb(_)));
+	}
+
+	#[test]
+	fn basic_level() {
+		let protanopia = Some(Deficiency::Protanopia);
+		let mut red = Chalk::new();
+		red.red();
+		assert!(red.for_deficiency(protanopia, ColorLevel::Basic).is_none());
+		assert!(red.for_deficiency(None, ColorLevel::TrueColor).is_none());
+
+		let daltonized =
+			red.for_deficiency(protanopia, ColorLevel::Ansi256).unwrap();
+		assert_ne!(daltonized.render_at(ColorLevel::Ansi256), "\x1b[31m");
+		assert_eq!(daltonized.render_at(ColorLevel::Basic), "\x1b[31m");
+	}
+}
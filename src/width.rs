@@ -0,0 +1,360 @@
+use std::borrow::Cow;
+
+/// Ranges of characters which take up two columns in a terminal
+const WIDE: [(u32, u32); 62] = [
+	(0x1100, 0x115f),
+	(0x231a, 0x231b),
+	(0x2329, 0x232a),
+	(0x23e9, 0x23ec),
+	(0x23f0, 0x23f0),
+	(0x23f3, 0x23f3),
+	(0x25fd, 0x25fe),
+	(0x2614, 0x2615),
+	(0x2648, 0x2653),
+	(0x267f, 0x267f),
+	(0x2693, 0x2693),
+	(0x26a1, 0x26a1),
+	(0x26aa, 0x26ab),
+	(0x26bd, 0x26be),
+	(0x26c4, 0x26c5),
+	(0x26ce, 0x26ce),
+	(0x26d4, 0x26d4),
+	(0x26ea, 0x26ea),
+	(0x26f2, 0x26f3),
+	(0x26f5, 0x26f5),
+	(0x26fa, 0x26fa),
+	(0x26fd, 0x26fd),
+	(0x2705, 0x2705),
+	(0x270a, 0x270b),
+	(0x2728, 0x2728),
+	(0x274c, 0x274c),
+	(0x274e, 0x274e),
+	(0x2753, 0x2755),
+	(0x2757, 0x2757),
+	(0x2795, 0x2797),
+	(0x27b0, 0x27b0),
+	(0x27bf, 0x27bf),
+	(0x2b1b, 0x2b1c),
+	(0x2b50, 0x2b50),
+	(0x2b55, 0x2b55),
+	(0x2e80, 0x303e),
+	(0x3041, 0x33ff),
+	(0x3400, 0x4dbf),
+	(0x4e00, 0x9fff),
+	(0xa000, 0xa4cf),
+	(0xa960, 0xa97f),
+	(0xac00, 0xd7a3),
+	(0xf900, 0xfaff),
+	(0xfe10, 0xfe19),
+	(0xfe30, 0xfe6f),
+	(0xff00, 0xff60),
+	(0xffe0, 0xffe6),
+	(0x16fe0, 0x16fe4),
+	(0x17000, 0x18aff),
+	(0x1b000, 0x1b2ff),
+	(0x1f004, 0x1f004),
+	(0x1f0cf, 0x1f0cf),
+	(0x1f18e, 0x1f18e),
+	(0x1f191, 0x1f19a),
+	(0x1f200, 0x1f2ff),
+	(0x1f300, 0x1f64f),
+	(0x1f680, 0x1f6ff),
+	(0x1f7e0, 0x1f7eb),
+	(0x1f90c, 0x1f9ff),
+	(0x1fa70, 0x1faff),
+	(0x20000, 0x2fffd),
+	(0x30000, 0x3fffd),
+];
+
+/// Ranges of combining marks and other characters which take up no space
+const ZERO_WIDTH: [(u32, u32); 44] = [
+	(0x0300, 0x036f),
+	(0x0483, 0x0489),
+	(0x0591, 0x05bd),
+	(0x05bf, 0x05bf),
+	(0x05c1, 0x05c2),
+	(0x05c4, 0x05c5),
+	(0x05c7, 0x05c7),
+	(0x0610, 0x061a),
+	(0x064b, 0x065f),
+	(0x0670, 0x0670),
+	(0x06d6, 0x06dc),
+	(0x06df, 0x06e4),
+	(0x06e7, 0x06e8),
+	(0x06ea, 0x06ed),
+	(0x0711, 0x0711),
+	(0x0730, 0x074a),
+	(0x0900, 0x0902),
+	(0x093a, 0x093a),
+	(0x093c, 0x093c),
+	(0x0941, 0x0948),
+	(0x094d, 0x094d),
+	(0x0951, 0x0957),
+	(0x0962, 0x0963),
+	(0x0e31, 0x0e31),
+	(0x0e34, 0x0e3a),
+	(0x0e47, 0x0e4e),
+	(0x1ab0, 0x1aff),
+	(0x1dc0, 0x1dff),
+	(0x200b, 0x200f),
+	(0x2060, 0x2064),
+	(0x20d0, 0x20ff),
+	(0x302a, 0x302d),
+	(0x3099, 0x309a),
+	(0xfe00, 0xfe0f),
+	(0xfe20, 0xfe2f),
+	(0xfeff, 0xfeff),
+	(0x1f3fb, 0x1f3ff),
+	(0xe0000, 0xe007f),
+	(0xe0100, 0xe01ef),
+	(0x0000, 0x001f),
+	(0x007f, 0x009f),
+	(0x00ad, 0x00ad),
+	(0x1160, 0x11ff),
+	(0xd7b0, 0xd7ff),
+];
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+fn in_table(c: char, table: &[(u32, u32)]) -> bool {
+	let c = c as u32;
+	table.iter().any(|&(start, end)| start <= c && c <= end)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+	('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// Gets the number of columns a character takes up in a terminal.
+///
+/// Combining marks and control characters take up no columns, and East Asian
+/// wide characters take up two.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::char_width;
+///
+/// assert_eq!(char_width('a'), 1);
+/// assert_eq!(char_width('漢'), 2);
+/// assert_eq!(char_width('\u{301}'), 0);
+/// ```
+pub fn char_width(c: char) -> usize {
+	if c == ZERO_WIDTH_JOINER || in_table(c, &ZERO_WIDTH) {
+		0
+	} else if in_table(c, &WIDE) {
+		2
+	} else {
+		1
+	}
+}
+
+/// The length of the escape sequence at the start of the text, if there is one
+fn escape_len(text: &str) -> Option<usize> {
+	let mut chars = text.char_indices();
+	let (_, first) = chars.next()?;
+	let kind = match first {
+		'\x1b' => chars.next().map(|(_, c)| c),
+		'\u{9b}' => Some('['),
+		'\u{9d}' => Some(']'),
+		_ => return None,
+	};
+
+	match kind {
+		// unterminated escape at the end of the text
+		None => Some(text.len()),
+		// control sequence: parameters, then a final byte
+		Some('[') => {
+			for (i, c) in chars {
+				if ('\x40'..='\x7e').contains(&c) {
+					return Some(i + c.len_utf8());
+				}
+			}
+			Some(text.len())
+		}
+		// strings terminated by BEL or ST
+		Some(']') | Some('P') | Some('X') | Some('^') | Some('_') => {
+			let mut chars = chars.peekable();
+			while let Some((i, c)) = chars.next() {
+				match c {
+					'\x07' | '\u{9c}' => return Some(i + c.len_utf8()),
+					'\x1b' if matches!(chars.peek(), Some((_, '\\'))) => {
+						return Some(i + 2);
+					}
+					_ => (),
+				}
+			}
+			Some(text.len())
+		}
+		// intermediate bytes, then a final byte
+		Some(c) if ('\x20'..='\x2f').contains(&c) => {
+			for (i, c) in chars {
+				if !('\x20'..='\x2f').contains(&c) {
+					return Some(i + c.len_utf8());
+				}
+			}
+			Some(text.len())
+		}
+		Some(c) => Some(first.len_utf8() + c.len_utf8()),
+	}
+}
+
+/// Removes any escape sequences from the text.
+///
+/// This includes SGR sequences written by [`crate::Chalk`], other control
+/// sequences, such as cursor movement, and operating system commands, such as
+/// hyperlinks. If the text doesn't contain any escape sequences, it is
+/// returned without being copied.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::strip_ansi;
+///
+/// let styled = "\x1b[31mred\x1b[39m and \x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\";
+/// assert_eq!(strip_ansi(styled), "red and link");
+/// ```
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+	let is_escape = |c: char| matches!(c, '\x1b' | '\u{9b}' | '\u{9d}');
+	let Some(first) = text.find(is_escape) else {
+		return Cow::Borrowed(text);
+	};
+
+	let mut stripped = String::with_capacity(text.len());
+	stripped.push_str(&text[..first]);
+
+	let mut rest = &text[first..];
+	while !rest.is_empty() {
+		match escape_len(rest) {
+			Some(len) => rest = &rest[len..],
+			None => {
+				let end = rest.find(is_escape).unwrap_or(rest.len());
+				stripped.push_str(&rest[..end]);
+				rest = &rest[end..];
+			}
+		}
+	}
+
+	Cow::Owned(stripped)
+}
+
+/// Gets the number of columns the text takes up in a terminal.
+///
+/// Escape sequences are ignored. Wide characters count as two columns, and
+/// combining marks, zero width joiners, and the characters joined by them
+/// count as none.
+///
+/// # Example
+///
+/// ```rust
+/// use chalk_rs::{visible_width, Chalk, ColorLevel};
+///
+/// let mut chalk = Chalk::new();
+/// let text = chalk.red().string_at(ColorLevel::TrueColor, &"日本 ok");
+/// assert_eq!(text.len(), 19);
+/// assert_eq!(visible_width(&text), 7);
+/// ```
+pub fn visible_width(text: &str) -> usize {
+	let text = strip_ansi(text);
+	let mut width = 0;
+	let mut previous = None;
+	let mut pending_flag = false;
+
+	for c in text.chars() {
+		width += match (previous, c) {
+			(Some(ZERO_WIDTH_JOINER), _) => 0,
+			(Some(p), EMOJI_PRESENTATION) if char_width(p) == 1 => 1,
+			_ if is_regional_indicator(c) => {
+				pending_flag = !pending_flag;
+				if pending_flag {
+					2
+				} else {
+					0
+				}
+			}
+			_ => char_width(c),
+		};
+
+		if !is_regional_indicator(c) {
+			pending_flag = false;
+		}
+		previous = Some(c);
+	}
+
+	width
+}
+
+/// Iterates over the groups of characters which are displayed together
+pub(crate) struct Graphemes<'a> {
+	rest: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+	type Item = &'a str;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let mut chars = self.rest.char_indices();
+		let (_, mut previous) = chars.next()?;
+		let mut flag = is_regional_indicator(previous);
+		let mut end = self.rest.len();
+
+		for (i, c) in chars {
+			let joined = previous == ZERO_WIDTH_JOINER
+				|| c == ZERO_WIDTH_JOINER
+				|| (char_width(c) == 0 && !c.is_control())
+				|| (flag && is_regional_indicator(c));
+			if !joined {
+				end = i;
+				break;
+			}
+
+			flag = false;
+			previous = c;
+		}
+
+		let (grapheme, rest) = self.rest.split_at(end);
+		self.rest = rest;
+		Some(grapheme)
+	}
+}
+
+/// Splits the text into graphemes, such as a letter with its combining marks,
+/// emoji joined by zero width joiners, or a pair of regional indicators
+pub(crate) fn graphemes(text: &str) -> Graphemes<'_> {
+	Graphemes { rest: text }
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn strips_escapes() {
+		assert!(matches!(strip_ansi("plain text"), Cow::Borrowed(_)));
+		assert_eq!(strip_ansi("\x1b[1;38;5;200mbold\x1b[m"), "bold");
+		assert_eq!(strip_ansi("a\x1b[2Kb\x1b(Bc"), "abc");
+		assert_eq!(strip_ansi("title\x1b]0;window\x07!"), "title!");
+		assert_eq!(strip_ansi("cut off\x1b["), "cut off");
+	}
+
+	#[test]
+	fn measures_width() {
+		assert_eq!(visible_width("hello"), 5);
+		assert_eq!(visible_width("こんにちは"), 10);
+		assert_eq!(visible_width("e\u{301}"), 1);
+		assert_eq!(visible_width("👩\u{200d}💻"), 2);
+		assert_eq!(visible_width("🇯🇵🇺🇸"), 4);
+		assert_eq!(visible_width("❤\u{fe0f}"), 2);
+	}
+
+	#[test]
+	fn splits_graphemes() {
+		let text = "e\u{301}👩\u{200d}💻🇯🇵🇺🇸\n";
+		assert_eq!(
+			graphemes(text).collect::<Vec<_>>(),
+			["e\u{301}", "👩\u{200d}💻", "🇯🇵", "🇺🇸", "\n"]
+		);
+	}
+}